@@ -1,9 +1,16 @@
 use super::Error;
+use crate::diagram::CodeRenderer;
+use crate::theme::ChapterTemplate;
 use mdbook_renderer::RenderContext;
+use pulldown_cmark::Options;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 pub const DEFAULT_TEMPLATE: &str = include_str!("index.hbs");
+pub const DEFAULT_TITLE_PAGE_TEMPLATE: &str = include_str!("title_page.hbs");
+pub const DEFAULT_COPYRIGHT_PAGE_TEMPLATE: &str = include_str!("copyright_page.hbs");
+pub const DEFAULT_COVER_TEMPLATE: &str = include_str!("cover.hbs");
+pub const COVER_PAGE_TEMPLATE: &str = include_str!("cover_page.hbs");
 
 /// The configuration struct used to tweak how an EPUB document is generated.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,49 +23,311 @@ pub struct Config {
     /// The template file to use when rendering individual chapters (relative
     /// to the book root).
     pub index_template: Option<PathBuf>,
+    /// A directory (relative to the book root) whose `*.hbs` files are registered
+    /// as Handlebars partials for all templates.
+    pub theme_dir: Option<PathBuf>,
+    /// Templates to render chapters with, picked by glob patterns matched against
+    /// the chapter path. The first matching pattern wins.
+    pub chapter_templates: Vec<ChapterTemplate>,
     /// A cover image to use for the epub.
     pub cover_image: Option<PathBuf>,
+    /// Generate an SVG cover from the book's title and authors when there is no
+    /// `cover-image`.
+    pub generate_cover: bool,
+    /// The SVG template for the generated cover (relative to the book root).
+    pub cover_template: Option<PathBuf>,
+    /// Background colour of the generated cover.
+    pub cover_background: String,
+    /// An image to fill the background of the generated cover with.
+    pub cover_background_image: Option<PathBuf>,
+    /// Text colour of the generated cover.
+    pub cover_text_color: String,
+    /// Add a page showing the cover image at the start of the book (default: true).
+    pub cover_page: bool,
+    /// Leave out chapters that fail to render, e.g. because of a template error,
+    /// instead of failing the build.
+    pub skip_failed_chapters: bool,
+    /// Add a placeholder page for draft chapters (the ones without a file) instead
+    /// of leaving them out.
+    pub draft_placeholders: bool,
     /// Additional assets to include in the ebook, such as typefaces.
     pub additional_resources: Vec<PathBuf>,
     /// Don't render section labels.
     pub no_section_label: bool,
     /// Use "smart quotes" instead of the usual `"` character.
     pub curly_quotes: bool,
-    /// Add backreference links to footnote definitions and allow pop-up footnote behaviour.
-    /// Requires `epub-version = 3`, in which case it is enabled by default.
+    /// The quotation marks used by `curly_quotes`. Picked from the book's (or
+    /// chapter's) language if not set.
+    pub quote_style: Option<QuoteStyle>,
+    /// Turn `--` into an en dash and `---` into an em dash.
+    pub smart_dashes: bool,
+    /// Turn `...` into an ellipsis.
+    pub smart_ellipses: bool,
+    /// Keep numbers and their units together with non-breaking spaces, and for
+    /// French text put them in front of `;:!?` and inside `« »` as well.
+    pub non_breaking_spaces: bool,
+    /// Insert soft hyphens into long words, using the hyphenation dictionary of
+    /// the book's (or chapter's) language.
+    pub hyphenate: bool,
+    /// The number of letters a word needs to get soft hyphens.
+    pub hyphenate_min_length: usize,
+    /// Convert TeX math to MathML. Defaults to the `mathjax-support` setting of
+    /// the HTML renderer.
+    pub math: Option<bool>,
+    /// The Markdown extensions to parse chapters with.
+    pub markdown: MarkdownExtensions,
+    /// Commands rendering the code blocks of some languages, e.g. `dot`, to
+    /// images.
+    pub code_renderers: Vec<CodeRenderer>,
+    /// Add a link to the Rust Playground under runnable Rust code blocks.
+    pub playground_links: bool,
+    /// Number the lines of all code blocks, not only of those with the `linenos`
+    /// attribute.
+    pub code_line_numbers: bool,
+    /// Wrap code lines longer than this number of characters, marking where they
+    /// continue.
+    pub code_wrap_column: Option<usize>,
+    /// Add backreference links to footnote definitions and, for EPUB 3, mark them up
+    /// as pop-up footnotes.
     pub footnote_backrefs: bool,
+    /// Put EPUB 3 footnotes into `<div>` rather than `<aside>` elements, as iBooks
+    /// doesn't display `<aside>`.
+    pub ibooks_footnotes: bool,
+    /// Collect the footnotes of all chapters into a notes chapter at the end of the
+    /// book, instead of putting them at the end of each chapter.
+    pub endnotes: bool,
+    /// Number the endnotes per chapter or continuously through the book.
+    pub endnote_numbering: EndnoteNumbering,
+    /// Title of the notes chapter.
+    pub endnotes_title: String,
     /// EPUB version to use if specified, otherwise defaults to the epub-builder default.
     pub epub_version: Option<u8>,
+    /// Glob patterns of chapters (relative to the book's `src` dir) to leave out
+    /// of the EPUB, together with their sub-chapters and assets.
+    pub exclude: Vec<String>,
+    /// Glob patterns of chapters to put into the EPUB. If empty, every chapter
+    /// not matched by `exclude` is included.
+    pub include: Vec<String>,
+    /// Renumber the chapters left after `include`/`exclude` instead of keeping
+    /// their original section numbers.
+    pub renumber_chapters: bool,
+    /// The chapter where reading starts (relative to the book's `src` dir).
+    /// Defaults to the first numbered chapter, skipping prefix chapters.
+    pub start_chapter: Option<PathBuf>,
+    /// Add a table of contents page to the book flow, after the cover and title pages.
+    pub inline_toc: bool,
+    /// Heading levels linked from the in-book table of contents: 1 lists the
+    /// chapters only, 2 adds their `h2` headings and 3 the `h3` headings as well.
+    pub inline_toc_depth: usize,
+    /// Heading levels listed in the EPUB navigation: 1 lists the chapters only,
+    /// 2 adds their `h2` headings, and so on down to 6.
+    pub toc_depth: usize,
+    /// Generate a title page ahead of the first chapter.
+    pub title_page: bool,
+    /// The template file for the title page (relative to the book root).
+    pub title_page_template: Option<PathBuf>,
+    /// Generate a copyright page after the title page.
+    pub copyright_page: bool,
+    /// The template file for the copyright page (relative to the book root).
+    pub copyright_page_template: Option<PathBuf>,
+    /// Subtitle of the book, shown on the title page.
+    pub subtitle: Option<String>,
+    /// Publisher of the book.
+    pub publisher: Option<String>,
+    /// An image shown on the title page, e.g. the publisher's logo.
+    pub logo: Option<PathBuf>,
+    /// Copyright statement of the book, also used as its rights metadata.
+    pub rights: Option<String>,
+    /// Edition of the book, e.g. "Second edition".
+    pub edition: Option<String>,
+    /// ISBN of the book.
+    pub isbn: Option<String>,
+}
+
+/// How endnotes are numbered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EndnoteNumbering {
+    /// Start again from 1 in every chapter.
+    Chapter,
+    /// Number continuously through the whole book.
+    Book,
+}
+
+/// The Markdown extensions of pulldown-cmark to enable, by default the ones
+/// mdbook's HTML renderer uses.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct MarkdownExtensions {
+    pub tables: bool,
+    pub footnotes: bool,
+    pub strikethrough: bool,
+    pub tasklists: bool,
+    pub heading_attributes: bool,
+    /// Curly quotes, dashes and ellipses. Defaults to the HTML renderer's setting.
+    pub smart_punctuation: Option<bool>,
+    /// Defaults to the HTML renderer's setting.
+    pub definition_lists: Option<bool>,
+    /// GitHub-style `> [!NOTE]` alerts. Defaults to the HTML renderer's setting.
+    pub admonitions: Option<bool>,
+    /// `^superscript^`.
+    pub superscript: bool,
+    /// `~subscript~`.
+    pub subscript: bool,
+    /// `[[wikilinks]]`.
+    pub wikilinks: bool,
+}
+
+impl Default for MarkdownExtensions {
+    fn default() -> MarkdownExtensions {
+        MarkdownExtensions {
+            tables: true,
+            footnotes: true,
+            strikethrough: true,
+            tasklists: true,
+            heading_attributes: true,
+            smart_punctuation: None,
+            definition_lists: None,
+            admonitions: None,
+            superscript: false,
+            subscript: false,
+            wikilinks: false,
+        }
+    }
+}
+
+/// The quotation marks to convert straight quotes to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum QuoteStyle {
+    /// “double” and ‘single’.
+    English,
+    /// „double“ and ‚single‘.
+    German,
+    /// « double » with narrow no-break spaces and “single”.
+    French,
+    /// «double» and „single“.
+    Russian,
+    /// ”double” and ’single’.
+    Swedish,
 }
 
 impl Config {
     /// Get the `output.epub` table from the provided `book.toml` config,
     /// falling back to the default if
     pub fn from_render_context(ctx: &RenderContext) -> Result<Config, Error> {
-        match ctx.config.get::<Config>("output.epub")? {
+        let mut cfg = match ctx.config.get::<Config>("output.epub")? {
             Some(mut cfg) => {
                 // make sure we update the `index_template` to make it relative
                 // to the book root
                 if let Some(template_file) = cfg.index_template.take() {
                     cfg.index_template = Some(ctx.root.join(template_file));
                 }
+                if let Some(theme_dir) = cfg.theme_dir.take() {
+                    cfg.theme_dir = Some(ctx.root.join(theme_dir));
+                }
+                if let Some(template_file) = cfg.title_page_template.take() {
+                    cfg.title_page_template = Some(ctx.root.join(template_file));
+                }
+                if let Some(template_file) = cfg.copyright_page_template.take() {
+                    cfg.copyright_page_template = Some(ctx.root.join(template_file));
+                }
+                if let Some(template_file) = cfg.cover_template.take() {
+                    cfg.cover_template = Some(ctx.root.join(template_file));
+                }
 
-                Ok(cfg)
+                cfg
             }
-            None => Ok(Config::default()),
+            None => Config::default(),
+        };
+        // books written for MathJax in the HTML output have math to convert
+        if cfg.math.is_none() {
+            cfg.math = ctx.config.get::<bool>("output.html.mathjax-support")?;
+        }
+        // parse Markdown the way the HTML renderer does
+        let markdown = &mut cfg.markdown;
+        for (setting, key) in [
+            (
+                &mut markdown.smart_punctuation,
+                "output.html.smart-punctuation",
+            ),
+            (
+                &mut markdown.definition_lists,
+                "output.html.definition-lists",
+            ),
+            (&mut markdown.admonitions, "output.html.admonitions"),
+        ] {
+            if setting.is_none() {
+                *setting = ctx.config.get::<bool>(key)?;
+            }
+        }
+
+        Ok(cfg)
+    }
+
+    /// The options to parse chapters with. Smart punctuation is left to
+    /// `curly_quotes` when it is set, as its quotes follow the book's language.
+    pub(crate) fn markdown_options(&self) -> Options {
+        let markdown = &self.markdown;
+        let mut options = Options::empty();
+        for (enabled, option) in [
+            (markdown.tables, Options::ENABLE_TABLES),
+            (markdown.footnotes, Options::ENABLE_FOOTNOTES),
+            (markdown.strikethrough, Options::ENABLE_STRIKETHROUGH),
+            (markdown.tasklists, Options::ENABLE_TASKLISTS),
+            (
+                markdown.heading_attributes,
+                Options::ENABLE_HEADING_ATTRIBUTES,
+            ),
+            (
+                markdown.smart_punctuation.unwrap_or(true) && !self.curly_quotes,
+                Options::ENABLE_SMART_PUNCTUATION,
+            ),
+            (
+                markdown.definition_lists.unwrap_or(true),
+                Options::ENABLE_DEFINITION_LIST,
+            ),
+            (markdown.admonitions.unwrap_or(true), Options::ENABLE_GFM),
+            (markdown.superscript, Options::ENABLE_SUPERSCRIPT),
+            (markdown.subscript, Options::ENABLE_SUBSCRIPT),
+            (markdown.wikilinks, Options::ENABLE_WIKILINKS),
+            (self.math.unwrap_or(false), Options::ENABLE_MATH),
+        ] {
+            options.set(option, enabled);
         }
+        options
     }
 
     pub fn template(&self) -> Result<String, Error> {
-        match self.index_template {
-            Some(ref filename) => {
-                let buffer = std::fs::read_to_string(filename)
-                    .map_err(|_| Error::OpenTemplate(filename.clone()))?;
+        read_template(&self.index_template, DEFAULT_TEMPLATE)
+    }
 
-                Ok(buffer)
-            }
-            None => Ok(DEFAULT_TEMPLATE.to_string()),
+    pub fn title_page_template(&self) -> Result<String, Error> {
+        read_template(&self.title_page_template, DEFAULT_TITLE_PAGE_TEMPLATE)
+    }
+
+    pub fn copyright_page_template(&self) -> Result<String, Error> {
+        read_template(
+            &self.copyright_page_template,
+            DEFAULT_COPYRIGHT_PAGE_TEMPLATE,
+        )
+    }
+
+    pub fn cover_template(&self) -> Result<String, Error> {
+        read_template(&self.cover_template, DEFAULT_COVER_TEMPLATE)
+    }
+}
+
+/// Read the user's template file, or use the built-in one if there is none.
+fn read_template(template_file: &Option<PathBuf>, default: &str) -> Result<String, Error> {
+    match template_file {
+        Some(filename) => {
+            let buffer = std::fs::read_to_string(filename)
+                .map_err(|_| Error::OpenTemplate(filename.clone()))?;
+
+            Ok(buffer)
         }
+        None => Ok(default.to_string()),
     }
 }
 
@@ -68,12 +337,55 @@ impl Default for Config {
             use_default_css: true,
             additional_css: Vec::new(),
             index_template: None,
+            theme_dir: None,
+            chapter_templates: Vec::new(),
             cover_image: None,
+            generate_cover: false,
+            cover_template: None,
+            cover_background: String::from("#1f3a5f"),
+            cover_background_image: None,
+            cover_text_color: String::from("#ffffff"),
+            cover_page: true,
+            skip_failed_chapters: false,
+            draft_placeholders: false,
             additional_resources: Vec::new(),
             no_section_label: false,
             curly_quotes: false,
+            quote_style: None,
+            smart_dashes: false,
+            smart_ellipses: false,
+            non_breaking_spaces: false,
+            hyphenate: false,
+            hyphenate_min_length: 8,
+            math: None,
+            markdown: MarkdownExtensions::default(),
+            code_renderers: Vec::new(),
+            playground_links: false,
+            code_line_numbers: false,
+            code_wrap_column: None,
             footnote_backrefs: false,
+            ibooks_footnotes: false,
+            endnotes: false,
+            endnote_numbering: EndnoteNumbering::Chapter,
+            endnotes_title: String::from("Notes"),
             epub_version: None,
+            exclude: Vec::new(),
+            include: Vec::new(),
+            renumber_chapters: false,
+            start_chapter: None,
+            inline_toc: false,
+            inline_toc_depth: 1,
+            toc_depth: 1,
+            title_page: false,
+            title_page_template: None,
+            copyright_page: false,
+            copyright_page_template: None,
+            subtitle: None,
+            publisher: None,
+            logo: None,
+            rights: None,
+            edition: None,
+            isbn: None,
         }
     }
 }
@@ -98,6 +410,61 @@ mod tests {
         assert!(config.is_ok());
     }
 
+    #[test]
+    fn test_markdown_options() {
+        let options = Config::default().markdown_options();
+        assert!(options.contains(Options::ENABLE_TABLES | Options::ENABLE_SMART_PUNCTUATION));
+        assert!(options.contains(Options::ENABLE_DEFINITION_LIST | Options::ENABLE_GFM));
+        assert!(!options.intersects(Options::ENABLE_SUPERSCRIPT | Options::ENABLE_MATH));
+
+        // the typography options take care of punctuation
+        let config = Config {
+            curly_quotes: true,
+            ..Config::default()
+        };
+        assert!(
+            !config
+                .markdown_options()
+                .contains(Options::ENABLE_SMART_PUNCTUATION)
+        );
+    }
+
+    #[test]
+    fn test_smart_dashes_keep_smart_quotes() {
+        let config = Config {
+            smart_dashes: true,
+            smart_ellipses: true,
+            ..Config::default()
+        };
+        let options = config.markdown_options();
+        assert!(options.contains(Options::ENABLE_SMART_PUNCTUATION));
+        let mut html = String::new();
+        pulldown_cmark::html::push_html(
+            &mut html,
+            pulldown_cmark::Parser::new_ext("He said \"hello\" -- it's fine.", options),
+        );
+        assert_eq!(html, "<p>He said “hello” – it’s fine.</p>\n");
+    }
+
+    #[test]
+    fn test_markdown_extensions_follow_html_renderer() {
+        let tmp_dir = TempDir::new().unwrap();
+        let mut json = ctx_with_template("src", tmp_dir.path());
+        json["config"]["output"]["html"] = json!({"smart-punctuation": false});
+        json["config"]["output"]["epub"]["markdown"] = json!({"definition-lists": false});
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let config = Config::from_render_context(&ctx).unwrap();
+        assert_eq!(config.markdown.smart_punctuation, Some(false));
+        assert_eq!(config.markdown.definition_lists, Some(false));
+        assert_eq!(config.markdown.admonitions, None);
+        let options = config.markdown_options();
+        assert!(
+            !options
+                .intersects(Options::ENABLE_SMART_PUNCTUATION | Options::ENABLE_DEFINITION_LIST)
+        );
+        assert!(options.contains(Options::ENABLE_GFM));
+    }
+
     fn ctx_with_template(source: &str, destination: &Path) -> serde_json::Value {
         json!({
             "version": mdbook_core::MDBOOK_VERSION,
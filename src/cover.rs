@@ -0,0 +1,136 @@
+/// Where the generated cover image is put in the EPUB.
+pub(crate) const GENERATED_COVER_PATH: &str = "cover.svg";
+/// Where the cover page showing the cover image is put in the EPUB.
+pub(crate) const COVER_PAGE_PATH: &str = "cover.html";
+/// Size of the generated cover, as set in the `cover` template.
+pub(crate) const GENERATED_COVER_SIZE: (u32, u32) = (1600, 2400);
+
+/// The cover image added to the EPUB.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CoverImage {
+    /// Path of the image in the EPUB, with forward slashes.
+    pub path: String,
+    /// Width and height in pixels, if known.
+    pub size: Option<(u32, u32)>,
+}
+
+impl CoverImage {
+    pub(crate) fn new(path: &str, size: Option<(u32, u32)>) -> Self {
+        CoverImage {
+            path: path.replace('\\', "/"),
+            size,
+        }
+    }
+}
+
+/// Read the width and height from the header of a PNG, GIF or JPEG image.
+pub(crate) fn image_size(data: &[u8]) -> Option<(u32, u32)> {
+    let be_u16 = |pos: usize| Some(u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]));
+    let be_u32 = |pos: usize| Some(u32::from_be_bytes(data.get(pos..pos + 4)?.try_into().ok()?));
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // the IHDR chunk comes first
+        return Some((be_u32(16)?, be_u32(20)?));
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        let le_u16 = |pos: usize| u16::from_le_bytes([data[pos], data[pos + 1]]) as u32;
+        return (data.len() >= 10).then(|| (le_u16(6), le_u16(8)));
+    }
+    if data.starts_with(&[0xFF, 0xD8]) {
+        // walk the segments up to the start of frame
+        let mut pos = 2;
+        while pos + 4 <= data.len() {
+            if data[pos] != 0xFF {
+                return None;
+            }
+            let marker = data[pos + 1];
+            let length = be_u16(pos + 2)? as usize;
+            let is_start_of_frame =
+                matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+            if is_start_of_frame {
+                return Some((be_u16(pos + 7)? as u32, be_u16(pos + 5)? as u32));
+            }
+            pos += 2 + length;
+        }
+    }
+    None
+}
+
+/// Longest line of the title on the generated cover, in characters.
+pub(crate) const TITLE_LINE_LENGTH: usize = 16;
+/// Longest line of the subtitle on the generated cover, in characters.
+pub(crate) const SUBTITLE_LINE_LENGTH: usize = 30;
+
+/// Break the text into lines at word boundaries, as SVG text doesn't wrap by
+/// itself. Words longer than a line are kept whole.
+pub(crate) fn wrap_text(text: &str, line_length: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > line_length {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_size() {
+        let logo = include_bytes!("../tests/long_book_example/src/assets/rust-logo.png");
+        let (width, height) = image_size(logo).unwrap();
+        assert!(width > 0 && height > 0);
+
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend(640u32.to_be_bytes());
+        png.extend(480u32.to_be_bytes());
+        assert_eq!(image_size(&png), Some((640, 480)));
+    }
+
+    #[test]
+    fn test_gif_and_jpeg_size() {
+        let gif = b"GIF89a\x20\x03\x58\x02";
+        assert_eq!(image_size(gif), Some((800, 600)));
+
+        let jpeg = [
+            0xFF, 0xD8, // start of image
+            0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00, // APP0 segment
+            0xFF, 0xC0, 0x00, 0x11, 0x08, 0x04, 0xB0, 0x03, 0x20, // SOF0, 1200 x 800
+        ];
+        assert_eq!(image_size(&jpeg), Some((800, 1200)));
+    }
+
+    #[test]
+    fn test_unknown_image_size() {
+        assert_eq!(
+            image_size(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            None
+        );
+        assert_eq!(image_size(b"\x89PNG"), None);
+        assert_eq!(image_size(&[0xFF, 0xD8, 0x00]), None);
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        assert_eq!(
+            wrap_text("The Rust Programming Language", 16),
+            vec!["The Rust", "Programming", "Language"]
+        );
+        assert_eq!(wrap_text("  Short  title ", 16), vec!["Short title"]);
+        assert_eq!(
+            wrap_text("Supercalifragilistic words", 10),
+            vec!["Supercalifragilistic", "words"]
+        );
+        assert!(wrap_text("", 16).is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{Error, file_io};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Directory in the EPUB the rendered code blocks are put into.
+pub(crate) const DIAGRAMS_DIR: &str = "diagrams";
+
+/// Render the code blocks of a language, e.g. `dot` or `mermaid`, to images with
+/// a local command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CodeRenderer {
+    /// The language of the fenced code blocks to render.
+    pub language: String,
+    /// The command to pipe the code block into, writing the image to its
+    /// standard output, e.g. `dot -Tsvg`. Arguments are split at whitespace.
+    pub command: String,
+    /// The format of the image the command writes.
+    #[serde(default)]
+    pub format: ImageFormat,
+}
+
+/// Image formats code blocks can be rendered to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    #[default]
+    Svg,
+    Png,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Svg => "svg",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl CodeRenderer {
+    /// Render a code block, returning the image's file name. Images are cached
+    /// in `cache_dir` by the hash of the command and the code, so unchanged code
+    /// blocks aren't rendered again.
+    pub(crate) fn render(
+        &self,
+        code: &str,
+        root: &Path,
+        cache_dir: &Path,
+    ) -> Result<String, Error> {
+        let file_name = format!("{}.{}", self.hash(code), self.format.extension());
+        let cached = cache_dir.join(&file_name);
+        if cached.is_file() {
+            debug!("Using the cached rendering {:?}", cached);
+            return Ok(file_name);
+        }
+
+        let image = self.run(code, root)?;
+        file_io(
+            std::fs::create_dir_all(cache_dir).and_then(|_| std::fs::write(&cached, image)),
+            "write-diagram",
+            &cached,
+        )?;
+        Ok(file_name)
+    }
+
+    /// Where a rendered image is cached.
+    pub(crate) fn cached_image(cache_dir: &Path, file_name: &str) -> PathBuf {
+        cache_dir.join(file_name)
+    }
+
+    fn hash(&self, code: &str) -> String {
+        let mut hasher = Sha256::new();
+        for part in [&self.language, &self.command, code] {
+            hasher.update(part.as_bytes());
+            hasher.update([0]);
+        }
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Pipe the code into the command, returning what it writes.
+    fn run(&self, code: &str, root: &Path) -> Result<Vec<u8>, Error> {
+        let error = |reason: String| Error::CodeRenderer {
+            language: self.language.clone(),
+            command: self.command.clone(),
+            reason,
+        };
+        let mut args = self.command.split_whitespace();
+        let program = args
+            .next()
+            .ok_or_else(|| error("the command is empty".to_string()))?;
+        debug!(
+            "Rendering a '{}' code block with {:?}",
+            self.language, self.command
+        );
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| error(e.to_string()))?;
+        // written from another thread, as the command may write its output before
+        // reading all of its input, filling both pipes if we waited
+        let writer = child.stdin.take().map(|mut stdin| {
+            let code = code.to_string();
+            std::thread::spawn(move || stdin.write_all(code.as_bytes()))
+        });
+        let output = child.wait_with_output().map_err(|e| error(e.to_string()))?;
+        let written = writer.map(|writer| writer.join());
+        if !output.status.success() {
+            return Err(error(format!(
+                "{}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        match written {
+            Some(Err(_)) => return Err(error("writing the code block failed".to_string())),
+            // a command may not read all of its input
+            Some(Ok(Err(e))) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                return Err(error(e.to_string()));
+            }
+            _ => {}
+        }
+        if output.stdout.is_empty() {
+            return Err(error("the command wrote no image".to_string()));
+        }
+        Ok(output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const SVG: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\"/>";
+
+    fn renderer(command: &str) -> CodeRenderer {
+        CodeRenderer {
+            language: "svg".to_string(),
+            command: command.to_string(),
+            format: ImageFormat::Svg,
+        }
+    }
+
+    #[test]
+    fn test_render_is_cached() {
+        let tmp_dir = TempDir::new().unwrap();
+        let cache_dir = tmp_dir.path().join("cache");
+        let file_name = renderer("cat")
+            .render(SVG, tmp_dir.path(), &cache_dir)
+            .unwrap();
+        assert!(file_name.ends_with(".svg"));
+        let cached = CodeRenderer::cached_image(&cache_dir, &file_name);
+        assert_eq!(std::fs::read_to_string(&cached).unwrap(), SVG);
+
+        // the same code isn't rendered again
+        std::fs::write(&cached, "cached").unwrap();
+        let again = renderer("cat")
+            .render(SVG, tmp_dir.path(), &cache_dir)
+            .unwrap();
+        assert_eq!(again, file_name);
+        assert_eq!(std::fs::read_to_string(&cached).unwrap(), "cached");
+
+        // other code or another command is
+        assert_ne!(
+            renderer("cat")
+                .render("<svg/>", tmp_dir.path(), &cache_dir)
+                .unwrap(),
+            file_name
+        );
+        assert_ne!(
+            renderer("cat -")
+                .render(SVG, tmp_dir.path(), &cache_dir)
+                .unwrap(),
+            file_name
+        );
+    }
+
+    #[test]
+    fn test_render_large_code_block() {
+        // more than fits into the pipes, so `cat` writes before reading it all
+        let code = format!("<svg>{}</svg>", "<g/>\n".repeat(200_000));
+        let tmp_dir = TempDir::new().unwrap();
+        let file_name = renderer("cat")
+            .render(&code, tmp_dir.path(), tmp_dir.path())
+            .unwrap();
+        let image = CodeRenderer::cached_image(tmp_dir.path(), &file_name);
+        assert_eq!(std::fs::read_to_string(image).unwrap(), code);
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let tmp_dir = TempDir::new().unwrap();
+        let error = renderer("false")
+            .render(SVG, tmp_dir.path(), tmp_dir.path())
+            .unwrap_err();
+        assert!(matches!(error, Error::CodeRenderer { .. }));
+        assert!(
+            renderer("no-such-diagram-tool")
+                .render(SVG, tmp_dir.path(), tmp_dir.path())
+                .is_err()
+        );
+        assert!(
+            renderer(" ")
+                .render(SVG, tmp_dir.path(), tmp_dir.path())
+                .is_err()
+        );
+    }
+}
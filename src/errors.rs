@@ -10,8 +10,28 @@ pub enum Error {
     #[error("{0}")]
     EpubDocCreate(String),
 
-    #[error("Could not parse the template")]
-    TemplateParse,
+    #[error("Could not parse the template '{template}'{}: {reason}", position(*.line, *.column))]
+    TemplateParse {
+        template: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        reason: String,
+    },
+
+    #[error("Could not render chapter '{chapter}' with the template '{template}'{}: {reason}", position(*.line, *.column))]
+    ChapterRender {
+        chapter: String,
+        template: String,
+        line: Option<usize>,
+        column: Option<usize>,
+        reason: String,
+    },
+
+    #[error("{} chapter(s) failed to render:{}", .0.len(), list(.0))]
+    ChapterFailures(Vec<Error>),
+
+    #[error("Invalid front matter in chapter '{chapter}': {source}")]
+    FrontMatter { chapter: String, source: Box<Error> },
 
     #[error("Content file was not found: \'{0}\'")]
     ContentFileNotFound(String),
@@ -28,12 +48,25 @@ pub enum Error {
     #[error("Unable to open template {0}")]
     OpenTemplate(PathBuf),
 
+    #[error("Theme directory {0} was not found")]
+    ThemeDir(PathBuf),
+
+    #[error("Could not load the hyphenation dictionary for '{language}': {reason}")]
+    HyphenationDictionary { language: String, reason: String },
+
+    #[error("Could not render a '{language}' code block with '{command}': {reason}")]
+    CodeRenderer {
+        language: String,
+        command: String,
+        reason: String,
+    },
+
     #[error("Unable to parse render context")]
     RenderContext,
 
     #[error("Failed to {action} '{path}': {source}")]
     AssetFileIo {
-        action: String,  // string action type: "open", "create", "read", "write"
+        action: String, // string action type: "open", "create", "read", "write"
         path: PathBuf,
         source: std::io::Error,
     },
@@ -73,9 +106,60 @@ pub enum Error {
     HttpError(#[from] Box<ureq::Error>),
     #[error(transparent)]
     MimeTypeError(#[from] FromStrError),
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
 
     #[error("Incorrect book 'title', impossible to create file with name: '{0}'")]
     EpubBookNameOrPath(String),
+
+    #[error("Invalid chapter glob pattern '{0}': {1}")]
+    GlobPattern(String, glob::PatternError),
+}
+
+impl Error {
+    /// A template couldn't be parsed, `template` being its path or name.
+    pub(crate) fn template_parse(
+        template: impl Into<String>,
+        e: handlebars::TemplateError,
+    ) -> Self {
+        let (line, column) = e.pos().unzip();
+        Error::TemplateParse {
+            template: template.into(),
+            line,
+            column,
+            reason: e.reason().to_string(),
+        }
+    }
+
+    /// Rendering a chapter with the template, `template` being its path or name,
+    /// failed.
+    pub(crate) fn chapter_render(
+        chapter: impl Into<String>,
+        template: impl Into<String>,
+        e: handlebars::RenderError,
+    ) -> Self {
+        Error::ChapterRender {
+            chapter: chapter.into(),
+            template: template.into(),
+            line: e.line_no,
+            column: e.column_no,
+            reason: e.reason().to_string(),
+        }
+    }
+}
+
+/// Where in a template an error is, if known.
+fn position(line: Option<usize>, column: Option<usize>) -> String {
+    match (line, column) {
+        (Some(line), Some(column)) => format!(" at line {line}, column {column}"),
+        (Some(line), None) => format!(" at line {line}"),
+        _ => String::new(),
+    }
+}
+
+/// The errors as a list, one per line.
+fn list(errors: &[Error]) -> String {
+    errors.iter().map(|e| format!("\n  - {e}")).collect()
 }
 
 impl From<ureq::Error> for Error {
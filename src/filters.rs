@@ -1,3 +1,11 @@
+pub(crate) mod admonition;
 pub(crate) mod asset_link;
+pub(crate) mod chapter_link;
+pub(crate) mod code_block;
+pub(crate) mod diagram;
 pub(crate) mod footnote;
-pub(crate) mod quote_converter;
+pub(crate) mod heading;
+pub(crate) mod hyphenate;
+pub(crate) mod math;
+pub(crate) mod playground;
+pub(crate) mod typography;
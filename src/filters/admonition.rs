@@ -0,0 +1,279 @@
+use mdbook_core::utils::{escape_html, escape_html_attribute};
+use pulldown_cmark::{BlockQuoteKind, CowStr, Event, Tag, TagEnd};
+use std::borrow::Cow;
+
+/// Renders GitHub-style `> [!NOTE]` blockquotes as admonitions, an `<aside>`
+/// (a `<div>` in EPUB 2, which has no `<aside>`) with a title.
+pub(crate) struct AdmonitionFilter {
+    element: &'static str,
+}
+
+impl AdmonitionFilter {
+    pub(crate) fn new(epub_version_3: bool) -> Self {
+        AdmonitionFilter {
+            element: element(epub_version_3),
+        }
+    }
+
+    pub(crate) fn apply<'a>(&self, event: Event<'a>) -> Event<'a> {
+        match event {
+            Event::Start(Tag::BlockQuote(Some(kind))) => {
+                let (class, title) = match kind {
+                    BlockQuoteKind::Note => ("note", "Note"),
+                    BlockQuoteKind::Tip => ("tip", "Tip"),
+                    BlockQuoteKind::Important => ("important", "Important"),
+                    BlockQuoteKind::Warning => ("warning", "Warning"),
+                    BlockQuoteKind::Caution => ("caution", "Caution"),
+                };
+                Event::Html(CowStr::from(start_tag(self.element, class, title)))
+            }
+            Event::End(TagEnd::BlockQuote(Some(_))) => {
+                Event::Html(CowStr::from(format!("</{}>\n", self.element)))
+            }
+            _ => event,
+        }
+    }
+}
+
+fn element(epub_version_3: bool) -> &'static str {
+    if epub_version_3 { "aside" } else { "div" }
+}
+
+/// The start tag of an admonition, followed by its title unless that's empty.
+fn start_tag(element: &str, class: &str, title: &str) -> String {
+    let mut tag = format!(
+        "<{element} class=\"admonition {}\">\n",
+        escape_html_attribute(class)
+    );
+    if !title.is_empty() {
+        tag.push_str(&format!(
+            "<p class=\"admonition-title\">{}</p>\n",
+            escape_html(title)
+        ));
+    }
+    tag
+}
+
+/// Turn mdbook-admonish style fenced blocks, like
+///
+/// ````text
+/// ```admonish warning "Mind the gap"
+/// Some *Markdown*.
+/// ```
+/// ````
+///
+/// into admonitions. This is done on the Markdown source, wrapping the block's
+/// content in HTML, so the content is rendered like the rest of the chapter.
+pub(crate) fn expand_admonish_blocks(content: &str, epub_version_3: bool) -> Cow<'_, str> {
+    if !content.contains("admonish") {
+        return Cow::Borrowed(content);
+    }
+    let element = element(epub_version_3);
+    let mut expanded = String::with_capacity(content.len());
+    let mut lines = content.split_inclusive('\n');
+    // the fence of the other code block the line is in, whose content is left alone
+    let mut open_fence: Option<&str> = None;
+
+    while let Some(line) = lines.next() {
+        if let Some(fence) = open_fence {
+            if is_closing_fence(line, fence) {
+                open_fence = None;
+            }
+            expanded.push_str(line);
+            continue;
+        }
+        let Some((fence, info)) = opening_fence(line) else {
+            expanded.push_str(line);
+            continue;
+        };
+        let Some(info) = admonish_info(info) else {
+            open_fence = Some(fence);
+            expanded.push_str(line);
+            continue;
+        };
+        let (class, title) = parse_info(info);
+        expanded.push_str(&start_tag(element, &class, &title));
+        expanded.push('\n');
+        let mut block = String::new();
+        for line in lines.by_ref() {
+            if is_closing_fence(line, fence) {
+                break;
+            }
+            block.push_str(line);
+        }
+        // admonitions may be nested, using longer fences for the outer ones
+        expanded.push_str(&expand_admonish_blocks(&block, epub_version_3));
+        if !expanded.ends_with('\n') {
+            expanded.push('\n');
+        }
+        expanded.push_str(&format!("\n</{element}>\n"));
+    }
+
+    Cow::Owned(expanded)
+}
+
+/// The fence of a line opening a fenced code block, and its info string.
+fn opening_fence(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let fence_char = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let fence_len = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    if fence_len < 3 {
+        return None;
+    }
+    let (fence, info) = trimmed.split_at(fence_len);
+    Some((fence, info.trim()))
+}
+
+/// The rest of the info string of an `admonish` code block.
+fn admonish_info(info: &str) -> Option<&str> {
+    let info = info.strip_prefix("admonish")?;
+    if !(info.is_empty() || info.starts_with(char::is_whitespace)) {
+        return None;
+    }
+    Some(info.trim())
+}
+
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let trimmed = line.trim();
+    let fence_char = fence.chars().next().unwrap_or('`');
+    trimmed.len() >= fence.len() && trimmed.chars().all(|c| c == fence_char)
+}
+
+/// The class and title from an info string like `warning "Title"` or
+/// `tip title="Title"`. Without a title, the capitalized kind is used.
+fn parse_info(info: &str) -> (String, String) {
+    let (kind, rest) = match info.split_once(char::is_whitespace) {
+        _ if info.starts_with('"') || info.starts_with("title=") => ("note", info),
+        Some((kind, rest)) => (kind, rest.trim_start()),
+        None if info.is_empty() => ("note", ""),
+        None => (info, ""),
+    };
+    let class: String = kind
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let title = rest
+        .strip_prefix('"')
+        .or_else(|| rest.find("title=\"").map(|pos| &rest[pos + 7..]))
+        .and_then(|quoted| quoted.split_once('"'))
+        .map(|(title, _)| title.to_string())
+        .unwrap_or_else(|| capitalize(&class));
+    (class, title)
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::utils::create_new_pull_down_parser;
+    use pulldown_cmark::html;
+
+    fn render(markdown: &str, epub_version_3: bool) -> String {
+        let filter = AdmonitionFilter::new(epub_version_3);
+        let markdown = expand_admonish_blocks(markdown, epub_version_3);
+        let events = create_new_pull_down_parser(&markdown, Config::default().markdown_options())
+            .map(|event| filter.apply(event));
+        let mut out = String::new();
+        html::push_html(&mut out, events);
+        out
+    }
+
+    #[test]
+    fn test_gfm_alerts() {
+        assert_eq!(
+            render("> [!WARNING]\n> Mind the *gap*.\n", true),
+            "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Warning</p>\n\
+             <p>Mind the <em>gap</em>.</p>\n</aside>\n"
+        );
+        assert_eq!(
+            render("> [!TIP]\n> Use a div.\n", false),
+            "<div class=\"admonition tip\">\n<p class=\"admonition-title\">Tip</p>\n\
+             <p>Use a div.</p>\n</div>\n"
+        );
+        // ordinary blockquotes stay as they are
+        assert_eq!(
+            render("> Quote\n", true),
+            "<blockquote>\n<p>Quote</p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn test_admonish_blocks() {
+        assert_eq!(
+            render(
+                "```admonish warning \"Mind the gap\"\nSome *text*.\n```\n\nAfter.\n",
+                true
+            ),
+            "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Mind the gap</p>\n\
+             <p>Some <em>text</em>.</p>\n</aside>\n<p>After.</p>\n"
+        );
+        assert_eq!(
+            render(
+                "~~~~admonish\nNested:\n```rust\nlet x = 1;\n```\n~~~~\n",
+                false
+            ),
+            "<div class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n\
+             <p>Nested:</p>\n<pre><code class=\"language-rust\">let x = 1;\n</code></pre>\n</div>\n"
+        );
+        // other code blocks are left alone
+        assert_eq!(
+            render("```admonished\nx\n```\n", true),
+            "<pre><code class=\"language-admonished\">x\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_admonish_blocks_in_other_code_blocks_are_left_alone() {
+        let sample = "````markdown\n```admonish warning\nMind the gap.\n```\n````\n";
+        assert_eq!(expand_admonish_blocks(sample, true), sample);
+        assert_eq!(
+            render(sample, true),
+            "<pre><code class=\"language-markdown\">```admonish warning\nMind the gap.\n```\n</code></pre>\n"
+        );
+        // a later admonish block is still expanded
+        let content = format!("~~~\n```admonish\n~~~\n\n{sample}\n```admonish tip\nTip.\n```\n");
+        let expanded = expand_admonish_blocks(&content, true);
+        assert_eq!(expanded.matches("<aside").count(), 1);
+        assert!(expanded.contains("<aside class=\"admonition tip\">"));
+    }
+
+    #[test]
+    fn test_nested_admonish_blocks() {
+        assert_eq!(
+            render(
+                "````admonish info\nOuter.\n```admonish tip\nInner.\n```\n````\n",
+                true
+            ),
+            "<aside class=\"admonition info\">\n<p class=\"admonition-title\">Info</p>\n\
+             <p>Outer.</p>\n<aside class=\"admonition tip\">\n<p class=\"admonition-title\">Tip</p>\n\
+             <p>Inner.</p>\n</aside>\n</aside>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_info() {
+        assert_eq!(parse_info(""), ("note".to_string(), "Note".to_string()));
+        assert_eq!(parse_info("tip"), ("tip".to_string(), "Tip".to_string()));
+        assert_eq!(
+            parse_info("danger title=\"Hot <stuff>\" collapsible=true"),
+            ("danger".to_string(), "Hot <stuff>".to_string())
+        );
+        assert_eq!(
+            parse_info("\"Just a title\""),
+            ("note".to_string(), "Just a title".to_string())
+        );
+        assert_eq!(parse_info("info \"\""), ("info".to_string(), String::new()));
+    }
+}
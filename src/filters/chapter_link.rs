@@ -0,0 +1,125 @@
+use crate::utils::normalize_path;
+use pulldown_cmark::{CowStr, Event, Tag};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// A link to an anchor in a chapter of the book.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AnchorLink {
+    /// Path of the target chapter's XHTML file, relative to the book's `src` dir.
+    pub chapter: PathBuf,
+    pub anchor: String,
+}
+
+/// Points links to other chapters (`other.md#anchor`) at their XHTML files, like
+/// mdbook's HTML renderer does, and collects the anchors in other chapters the
+/// chapter links to.
+pub(crate) struct ChapterLinkFilter {
+    /// Path of the chapter's XHTML file, relative to the book's `src` dir.
+    chapter: PathBuf,
+    anchor_links: Vec<AnchorLink>,
+}
+
+impl ChapterLinkFilter {
+    pub(crate) fn new(chapter_path: &Path) -> Self {
+        ChapterLinkFilter {
+            chapter: chapter_path.with_extension("html"),
+            anchor_links: Vec::new(),
+        }
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Event<'a> {
+        match event {
+            Event::Start(Tag::Link {
+                link_type,
+                dest_url,
+                title,
+                id,
+            }) => Event::Start(Tag::Link {
+                link_type,
+                dest_url: self.fix_link(dest_url),
+                title,
+                id,
+            }),
+            _ => event,
+        }
+    }
+
+    pub(crate) fn into_anchor_links(self) -> Vec<AnchorLink> {
+        self.anchor_links
+    }
+
+    fn fix_link<'a>(&mut self, dest_url: CowStr<'a>) -> CowStr<'a> {
+        if dest_url.starts_with('/') || Url::parse(&dest_url).is_ok() {
+            return dest_url;
+        }
+        let (path, anchor) = match dest_url.split_once('#') {
+            Some((path, anchor)) => (path, Some(anchor)),
+            None => (dest_url.as_ref(), None),
+        };
+
+        let Some(stem) = path.strip_suffix(".md") else {
+            return dest_url;
+        };
+
+        let html_path = format!("{stem}.html");
+        if let Some(anchor) = anchor {
+            let chapter_dir = self.chapter.parent().unwrap_or(Path::new(""));
+            self.anchor_links.push(AnchorLink {
+                chapter: normalize_path(&chapter_dir.join(&html_path)),
+                anchor: anchor.to_string(),
+            });
+        }
+        match anchor {
+            Some(anchor) => CowStr::from(format!("{html_path}#{anchor}")),
+            None => CowStr::from(html_path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::LinkType;
+
+    fn link(dest_url: &str) -> Event<'_> {
+        Event::Start(Tag::Link {
+            link_type: LinkType::Inline,
+            dest_url: CowStr::from(dest_url),
+            title: CowStr::from(""),
+            id: CowStr::from(""),
+        })
+    }
+
+    #[test]
+    fn test_chapter_links_point_at_xhtml() {
+        let mut filter = ChapterLinkFilter::new(Path::new("guide/intro.md"));
+        assert_eq!(filter.apply(link("setup.md")), link("setup.html"));
+        assert_eq!(
+            filter.apply(link("../reference.md#options")),
+            link("../reference.html#options")
+        );
+        assert_eq!(filter.apply(link("#usage")), link("#usage"));
+        assert_eq!(
+            filter.into_anchor_links(),
+            vec![AnchorLink {
+                chapter: PathBuf::from("reference.html"),
+                anchor: "options".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_other_links_untouched() {
+        let mut filter = ChapterLinkFilter::new(Path::new("intro.md"));
+        for url in [
+            "https://example.com/readme.md",
+            "/absolute.md",
+            "image.png",
+            "mailto:someone@example.com",
+        ] {
+            assert_eq!(filter.apply(link(url)), link(url));
+        }
+        assert!(filter.into_anchor_links().is_empty());
+    }
+}
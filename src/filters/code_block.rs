@@ -0,0 +1,184 @@
+use crate::utils;
+use mdbook_core::utils::escape_html;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+
+/// Renders code blocks line by line, so that the lines can be numbered and
+/// lines too long for narrow e-reader screens can be wrapped.
+pub(crate) struct CodeBlockFilter {
+    line_numbers: bool,
+    wrap_column: Option<usize>,
+    current: Option<Listing>,
+}
+
+/// A code block being collected.
+struct Listing {
+    language: Option<String>,
+    numbered: bool,
+    code: String,
+}
+
+impl CodeBlockFilter {
+    /// Number the lines of all code blocks, or only of those with mdbook's
+    /// `linenos` attribute, and wrap lines longer than `wrap_column` characters.
+    pub(crate) fn new(line_numbers: bool, wrap_column: Option<usize>) -> Self {
+        CodeBlockFilter {
+            line_numbers,
+            wrap_column: wrap_column.filter(|&column| column > 0),
+            current: None,
+        }
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Option<Event<'a>> {
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                let (language, numbered) = match &kind {
+                    CodeBlockKind::Fenced(info) => {
+                        let mut attributes = utils::code_block_attributes(info);
+                        let language = attributes.next().map(str::to_string);
+                        let linenos = attributes.any(|attribute| attribute == "linenos");
+                        (language, self.line_numbers || linenos)
+                    }
+                    CodeBlockKind::Indented => (None, self.line_numbers),
+                };
+                if !numbered && self.wrap_column.is_none() {
+                    return Some(Event::Start(Tag::CodeBlock(kind)));
+                }
+                self.current = Some(Listing {
+                    language,
+                    numbered,
+                    code: String::new(),
+                });
+                None
+            }
+            Event::Text(text) if self.current.is_some() => {
+                if let Some(listing) = &mut self.current {
+                    listing.code.push_str(&text);
+                }
+                None
+            }
+            Event::End(TagEnd::CodeBlock) => match self.current.take() {
+                Some(listing) => Some(Event::Html(CowStr::from(self.render(&listing)))),
+                None => Some(event),
+            },
+            _ => Some(event),
+        }
+    }
+
+    fn render(&self, listing: &Listing) -> String {
+        let mut classes = Vec::new();
+        if let Some(language) = &listing.language {
+            classes.push(format!("language-{}", escape_html(language)));
+        }
+        if listing.numbered {
+            classes.push(String::from("numbered"));
+        }
+        let mut html = if classes.is_empty() {
+            String::from("<pre><code>")
+        } else {
+            format!("<pre><code class=\"{}\">", classes.join(" "))
+        };
+        for line in listing.code.lines() {
+            html.push_str("<span class=\"line\">");
+            let mut segments = self.wrap(line).into_iter();
+            if let Some(first) = segments.next() {
+                html.push_str(&escape_html(first));
+            }
+            for segment in segments {
+                html.push_str("\n<span class=\"continuation\">");
+                html.push_str(&escape_html(segment));
+                html.push_str("</span>");
+            }
+            html.push_str("</span>\n");
+        }
+        html.push_str("</code></pre>\n");
+        html
+    }
+
+    /// Split a line into pieces of at most `wrap_column` characters, breaking
+    /// after a space where there is one.
+    fn wrap<'l>(&self, mut line: &'l str) -> Vec<&'l str> {
+        let Some(column) = self.wrap_column else {
+            return vec![line];
+        };
+        let mut segments = Vec::new();
+        while let Some((limit, _)) = line.char_indices().nth(column) {
+            let end = match line[..limit].rfind(' ') {
+                Some(space) if !line[..space].trim_start().is_empty() => space + 1,
+                _ => limit,
+            };
+            segments.push(&line[..end]);
+            line = &line[end..];
+        }
+        segments.push(line);
+        segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(content: &str, line_numbers: bool, wrap_column: Option<usize>) -> String {
+        let mut filter = CodeBlockFilter::new(line_numbers, wrap_column);
+        let mut body = String::new();
+        html::push_html(
+            &mut body,
+            Parser::new(content).filter_map(|event| filter.apply(event)),
+        );
+        body
+    }
+
+    #[test]
+    fn test_code_blocks_are_left_alone_by_default() {
+        let content = "```rust\nlet x = 1;\n```\n";
+        assert_eq!(
+            render(content, false, None),
+            "<pre><code class=\"language-rust\">let x = 1;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_line_numbers() {
+        let content = "```rust\nlet x = 1;\nlet y = x < 2;\n```\n\n    indented\n";
+        assert_eq!(
+            render(content, true, None),
+            "<pre><code class=\"language-rust numbered\"><span class=\"line\">let x = 1;</span>\n<span class=\"line\">let y = x &lt; 2;</span>\n</code></pre>\n<pre><code class=\"numbered\"><span class=\"line\">indented</span>\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_linenos_attribute() {
+        let content = "```rust,linenos\nlet x = 1;\n```\n\n```rust\nlet y = 2;\n```\n";
+        let body = render(content, false, None);
+        assert!(body.contains(
+            "<code class=\"language-rust numbered\"><span class=\"line\">let x = 1;</span>"
+        ));
+        assert!(body.contains("<code class=\"language-rust\">let y = 2;"));
+
+        for info in ["rust linenos", "rust, ignore, linenos", "rust\tlinenos"] {
+            let body = render(&format!("```{info}\nlet x = 1;\n```\n"), false, None);
+            assert!(
+                body.contains("<code class=\"language-rust numbered\">"),
+                "{info}: {body}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_wrap_long_lines() {
+        let content = "```\nlet numbers = vec![1, 2, 3];\nshort\nabcdefghijklmnop\n```\n";
+        assert_eq!(
+            render(content, false, Some(12)),
+            "<pre><code><span class=\"line\">let numbers \n<span class=\"continuation\">= vec![1, </span>\n<span class=\"continuation\">2, 3];</span></span>\n<span class=\"line\">short</span>\n<span class=\"line\">abcdefghijkl\n<span class=\"continuation\">mnop</span></span>\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_wrap_keeps_indentation_together() {
+        let filter = CodeBlockFilter::new(false, Some(6));
+        assert_eq!(filter.wrap("    abcdefgh"), vec!["    ab", "cdefgh"]);
+        assert_eq!(filter.wrap("ab cd"), vec!["ab cd"]);
+        assert_eq!(CodeBlockFilter::new(false, Some(0)).wrap_column, None);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::Error;
+use crate::diagram::{CodeRenderer, DIAGRAMS_DIR};
+use crate::utils;
+use mdbook_core::utils::escape_html_attribute;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use std::path::Path;
+
+/// Replaces the fenced code blocks of the languages with a configured renderer,
+/// e.g. `dot`, by the image the renderer makes of them.
+pub(crate) struct DiagramFilter<'a> {
+    renderers: &'a [CodeRenderer],
+    root: &'a Path,
+    cache_dir: &'a Path,
+    path_to_root: &'a str,
+    /// The renderer and the code of the block being collected.
+    current: Option<(&'a CodeRenderer, String)>,
+    images: Vec<String>,
+    error: Option<Error>,
+}
+
+impl<'a> DiagramFilter<'a> {
+    pub(crate) fn new(
+        renderers: &'a [CodeRenderer],
+        root: &'a Path,
+        cache_dir: &'a Path,
+        path_to_root: &'a str,
+    ) -> Self {
+        DiagramFilter {
+            renderers,
+            root,
+            cache_dir,
+            path_to_root,
+            current: None,
+            images: Vec::new(),
+            error: None,
+        }
+    }
+
+    pub(crate) fn apply<'b>(&mut self, event: Event<'b>) -> Option<Event<'b>> {
+        match (&mut self.current, event) {
+            (None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => {
+                let language = utils::code_block_attributes(&info)
+                    .next()
+                    .unwrap_or_default();
+                match self.renderers.iter().find(|r| r.language == language) {
+                    Some(renderer) => {
+                        self.current = Some((renderer, String::new()));
+                        None
+                    }
+                    None => Some(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))),
+                }
+            }
+            (Some((_, code)), Event::Text(text)) => {
+                code.push_str(&text);
+                None
+            }
+            (Some(_), Event::End(TagEnd::CodeBlock)) => {
+                let (renderer, code) = self.current.take()?;
+                Some(Event::Html(CowStr::from(self.render(renderer, &code))))
+            }
+            (Some(_), _) => None,
+            (None, event) => Some(event),
+        }
+    }
+
+    fn render(&mut self, renderer: &CodeRenderer, code: &str) -> String {
+        if self.error.is_some() {
+            return String::new();
+        }
+        match renderer.render(code, self.root, self.cache_dir) {
+            Ok(file_name) => {
+                let html = format!(
+                    "<div class=\"diagram\"><img src=\"{}{DIAGRAMS_DIR}/{}\" alt=\"{} diagram\" /></div>\n",
+                    self.path_to_root,
+                    file_name,
+                    escape_html_attribute(&renderer.language)
+                );
+                self.images.push(file_name);
+                html
+            }
+            Err(error) => {
+                self.error = Some(error);
+                String::new()
+            }
+        }
+    }
+
+    /// The file names of the images rendered, or the first error.
+    pub(crate) fn into_images(self) -> Result<Vec<String>, Error> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.images),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::ImageFormat;
+    use pulldown_cmark::{Parser, html};
+    use tempfile::TempDir;
+
+    fn render(content: &str, command: &str) -> (String, Result<Vec<String>, Error>) {
+        let tmp_dir = TempDir::new().unwrap();
+        let renderers = [CodeRenderer {
+            language: "svg".to_string(),
+            command: command.to_string(),
+            format: ImageFormat::Svg,
+        }];
+        let mut filter = DiagramFilter::new(&renderers, tmp_dir.path(), tmp_dir.path(), "../");
+        let mut body = String::new();
+        html::push_html(
+            &mut body,
+            Parser::new(content).filter_map(|event| filter.apply(event)),
+        );
+        (body, filter.into_images())
+    }
+
+    #[test]
+    fn test_render_code_block() {
+        let content = "# Diagram\n\n```svg\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n```\n\n```rust\nfn main() {}\n```\n";
+        let (body, images) = render(content, "cat");
+        let images = images.unwrap();
+        assert_eq!(images.len(), 1);
+        assert!(body.contains(&format!(
+            "<div class=\"diagram\"><img src=\"../diagrams/{}\" alt=\"svg diagram\" /></div>",
+            images[0]
+        )));
+        assert!(!body.contains("&lt;svg"));
+        assert!(body.contains("<pre><code class=\"language-rust\">fn main() {}"));
+    }
+
+    #[test]
+    fn test_render_code_block_with_attributes() {
+        let content = "```svg\tlinenos\n<svg xmlns=\"http://www.w3.org/2000/svg\"/>\n```\n";
+        let (body, images) = render(content, "cat");
+        assert_eq!(images.unwrap().len(), 1);
+        assert!(body.contains("<div class=\"diagram\">"));
+    }
+
+    #[test]
+    fn test_render_error() {
+        let (_, images) = render("```svg\n<svg/>\n```\n", "false");
+        assert!(matches!(images, Err(Error::CodeRenderer { .. })));
+    }
+}
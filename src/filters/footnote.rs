@@ -2,12 +2,67 @@ use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
 use std::collections::HashMap;
 use std::fmt::Write as _;
 
+/// Where the footnotes of a chapter go when they are collected into a notes page.
+#[derive(Debug, Clone)]
+pub(crate) struct NoteLinks {
+    /// Link to the notes page, relative to the chapter.
+    pub notes_href: String,
+    /// Link to the chapter, relative to the notes page.
+    pub chapter_href: String,
+    /// Prefix making the note ids of the chapter unique in the book.
+    pub id_prefix: String,
+    /// Number of the chapter's first note.
+    pub first_number: usize,
+}
+
+impl Default for NoteLinks {
+    fn default() -> Self {
+        NoteLinks {
+            notes_href: String::new(),
+            chapter_href: String::new(),
+            id_prefix: String::new(),
+            first_number: 1,
+        }
+    }
+}
+
+/// How footnote references and definitions are marked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NoteMarkup {
+    /// Plain hyperlinks, for EPUB 2.
+    Plain,
+    /// EPUB 3 `noteref` links to notes in `<aside>` elements, which readers show as pop-ups.
+    Aside,
+    /// EPUB 3 `noteref` links to notes in `<div>` elements, as iBooks doesn't display `<aside>`.
+    Div,
+}
+
+impl NoteMarkup {
+    /// Attributes of the link to a note.
+    fn reference_attributes(self) -> &'static str {
+        match self {
+            NoteMarkup::Plain => "",
+            NoteMarkup::Aside | NoteMarkup::Div => r#" epub:type="noteref""#,
+        }
+    }
+
+    fn element(self) -> &'static str {
+        match self {
+            NoteMarkup::Aside => "aside",
+            NoteMarkup::Plain | NoteMarkup::Div => "div",
+        }
+    }
+}
+
 /// Reusable footnote filter.
 pub(crate) struct FootnoteFilter<'a> {
     footnotes: Vec<Vec<Event<'a>>>,
     in_footnote: Vec<Vec<Event<'a>>>,
     footnote_numbers: HashMap<CowStr<'a>, (usize, usize)>,
     is_enabled: bool,
+    links: NoteLinks,
+    note_type: &'static str,
+    markup: NoteMarkup,
 }
 impl<'a> FootnoteFilter<'a> {
     pub fn new(is_enabled: bool) -> Self {
@@ -16,6 +71,26 @@ impl<'a> FootnoteFilter<'a> {
             in_footnote: Vec::new(),
             footnote_numbers: HashMap::new(),
             is_enabled,
+            links: NoteLinks::default(),
+            note_type: "footnote",
+            markup: NoteMarkup::Div,
+        }
+    }
+    /// Endnotes never go into `<aside>` elements: readers hide those, which would
+    /// leave the notes chapter blank.
+    pub fn with_markup(self, markup: NoteMarkup) -> Self {
+        let markup = match markup {
+            NoteMarkup::Aside if self.note_type == "endnote" => NoteMarkup::Div,
+            _ => markup,
+        };
+        Self { markup, ..self }
+    }
+    /// Collect the footnotes as endnotes, linked to and from the notes page.
+    pub fn endnotes(links: NoteLinks) -> Self {
+        Self {
+            links,
+            note_type: "endnote",
+            ..Self::new(true)
         }
     }
     pub fn apply(&mut self, event: Event<'a>) -> Option<Event<'a>> {
@@ -32,13 +107,19 @@ impl<'a> FootnoteFilter<'a> {
                 None
             }
             Event::FootnoteReference(name) => {
-                let n = self.footnote_numbers.len() + 1;
+                let n = self.footnote_numbers.len() + self.links.first_number;
                 let (n, nr) = self
                     .footnote_numbers
                     .entry(name.clone())
                     .or_insert((n, 0usize));
                 *nr += 1;
-                let html = Event::Html(format!(r##"<sup class="footnote-reference" id="fr-{name}-{nr}"><a href="#fn-{name}">[{n}]</a></sup>"##).into());
+                let NoteLinks {
+                    notes_href,
+                    id_prefix,
+                    ..
+                } = &self.links;
+                let attributes = self.markup.reference_attributes();
+                let html = Event::Html(format!(r##"<sup class="footnote-reference" id="fr-{id_prefix}{name}-{nr}"><a{attributes} href="{notes_href}#fn-{id_prefix}{name}">[{n}]</a></sup>"##).into());
                 if self.in_footnote.is_empty() {
                     Some(html)
                 } else {
@@ -56,6 +137,10 @@ impl<'a> FootnoteFilter<'a> {
     pub(crate) fn is_empty(&self) -> bool {
         self.footnotes.is_empty()
     }
+    /// Number of distinct footnotes referenced in the chapter.
+    pub(crate) fn note_count(&self) -> usize {
+        self.footnote_numbers.len()
+    }
     fn move_from_in_to_footnotes(&mut self, event: Event<'a>) {
         let popped_vector = self.in_footnote.pop();
         match popped_vector {
@@ -90,6 +175,14 @@ impl<'a> FootnoteFilter<'a> {
         });
     }
     pub(crate) fn get_events(&self) -> impl Iterator<Item = Event<'_>> {
+        let NoteLinks {
+            chapter_href,
+            id_prefix,
+            ..
+        } = &self.links;
+        let element = self.markup.element();
+        let markup = self.markup;
+        let note_type = self.note_type;
         self.footnotes
             .clone()
             .into_iter()
@@ -139,15 +232,16 @@ impl<'a> FootnoteFilter<'a> {
                                 Event::Html("<p>".into())
                             } else {
                                 // At this point we have started rendering a Tag::FootnoteDefinition, so already wrote an
-                                // opening <div> tag, and starting to write the paragraphs of the definition.
+                                // opening <div> or <aside> tag, and starting to write the paragraphs of the definition.
                                 //
                                 // If we haven't written this footnote reference number yet, then write it at the beginning of
                                 // the paragraph in a <span>.
                                 //
-                                // This will include the footnote number in the <div>, but NOT as a block element, and
+                                // This will include the footnote number in the note, but NOT as a block element, and
                                 // hence it correcly shows up in footnote pop-ups.
                                 //
-                                // Use a <div> instead of an <aside> tag, because iBooks doesn't display <aside>.
+                                // EPUB 3 notes go into an <aside> tag for pop-ups, or a <div> with `NoteMarkup::Div`,
+                                // because iBooks doesn't display <aside>.
                                 //
                                 // Tested on: ReadEra and Moon+ Reader on Android, Kindle Paperwhite, iBooks, KOReader on ReMarkable 2.
                                 _written_footnote_numbers.push(fn_number);
@@ -157,7 +251,14 @@ impl<'a> FootnoteFilter<'a> {
                         Event::Start(Tag::FootnoteDefinition(current_name)) => {
                             name = current_name;
                             has_written_backrefs = false;
-                            Event::Html(format!(r##"<div class="footnote-definition" id="fn-{name}" epub:type="footnote">"##).into())
+                            // EPUB 2 has no `epub:type`
+                            let epub_type = match markup {
+                                NoteMarkup::Plain => String::new(),
+                                NoteMarkup::Aside | NoteMarkup::Div => {
+                                    format!(r#" epub:type="{note_type}""#)
+                                }
+                            };
+                            Event::Html(format!(r##"<{element} class="footnote-definition" id="fn-{id_prefix}{name}"{epub_type}>"##).into())
                         }
                         Event::End(TagEnd::FootnoteDefinition) | Event::End(TagEnd::Paragraph)
                             if !has_written_backrefs && i >= fl_len - 2 =>
@@ -168,25 +269,27 @@ impl<'a> FootnoteFilter<'a> {
                             );
                             for usage in 1..=usage_count {
                                 if usage == 1 {
-                                    write!(&mut end, r##" <a href="#fr-{name}-{usage}">↩</a>"##)
+                                    write!(&mut end, r##" <a href="{chapter_href}#fr-{id_prefix}{name}-{usage}">↩</a>"##)
                                         .unwrap();
                                 } else {
                                     write!(
                                         &mut end,
-                                        r##" <a href="#fr-{name}-{usage}">↩{usage}</a>"##
+                                        r##" <a href="{chapter_href}#fr-{id_prefix}{name}-{usage}">↩{usage}</a>"##
                                     )
                                     .unwrap();
                                 }
                             }
                             has_written_backrefs = true;
                             if internal_event == Event::End(TagEnd::FootnoteDefinition) {
-                                end.push_str("</div>\n");
+                                writeln!(&mut end, "</{element}>").unwrap();
                             } else {
                                 end.push_str("</p>\n");
                             }
                             Event::Html(end.into())
                         }
-                        Event::End(TagEnd::FootnoteDefinition) => Event::Html("</div>\n".into()),
+                        Event::End(TagEnd::FootnoteDefinition) => {
+                            Event::Html(format!("</{element}>\n").into())
+                        }
                         Event::FootnoteReference(_) => unreachable!("converted to HTML earlier"),
                         f => f,
                     })
@@ -288,6 +391,103 @@ mod tests {
         assert!(filter.footnotes.is_empty());
     }
 
+    #[test]
+    fn test_endnote_links() {
+        let input =
+            "Cited[^src] twice[^src] and[^other].\n\n[^src]: The source\n\n[^other]: Another";
+        let mut filter = FootnoteFilter::endnotes(NoteLinks {
+            notes_href: "../notes.html".to_string(),
+            chapter_href: "guide/setup.html".to_string(),
+            id_prefix: "guide-setup-".to_string(),
+            first_number: 4,
+        });
+        let mut body = String::new();
+        pulldown_cmark::html::push_html(
+            &mut body,
+            Parser::new_ext(input, Options::ENABLE_FOOTNOTES).filter_map(|e| filter.apply(e)),
+        );
+        assert!(body.contains(r##"<sup class="footnote-reference" id="fr-guide-setup-src-2"><a epub:type="noteref" href="../notes.html#fn-guide-setup-src">[4]</a></sup>"##));
+        assert!(body.contains(
+            r##"<a epub:type="noteref" href="../notes.html#fn-guide-setup-other">[5]</a>"##
+        ));
+        assert_eq!(filter.note_count(), 2);
+
+        filter.retain();
+        filter.sort_by_cached_key();
+        let mut notes = String::new();
+        pulldown_cmark::html::push_html(&mut notes, filter.get_events());
+        assert!(notes.contains(
+            r##"<div class="footnote-definition" id="fn-guide-setup-src" epub:type="endnote">"##
+        ));
+        assert!(notes.contains(r##"<span class="footnote-definition-label">[4]</span> The source <a href="guide/setup.html#fr-guide-setup-src-1">↩</a> <a href="guide/setup.html#fr-guide-setup-src-2">↩2</a>"##));
+    }
+
+    #[test]
+    fn test_endnotes_are_not_asides() {
+        let input = "Text[^a].\n\n[^a]: The note";
+        let mut filter = FootnoteFilter::endnotes(NoteLinks {
+            notes_href: "notes.html".to_string(),
+            chapter_href: "intro.html".to_string(),
+            id_prefix: "intro-".to_string(),
+            first_number: 1,
+        })
+        .with_markup(NoteMarkup::Aside);
+        let mut body = String::new();
+        pulldown_cmark::html::push_html(
+            &mut body,
+            Parser::new_ext(input, Options::ENABLE_FOOTNOTES).filter_map(|e| filter.apply(e)),
+        );
+        assert!(body.contains(r##"<a epub:type="noteref" href="notes.html#fn-intro-a">[1]</a>"##));
+
+        filter.retain();
+        filter.sort_by_cached_key();
+        let mut notes = String::new();
+        pulldown_cmark::html::push_html(&mut notes, filter.get_events());
+        assert!(notes.contains(
+            r##"<div class="footnote-definition" id="fn-intro-a" epub:type="endnote">"##
+        ));
+        assert!(!notes.contains("<aside"));
+    }
+
+    #[test]
+    fn test_note_markup() {
+        let input = "Text[^a].\n\n[^a]: The note";
+        let render = |markup| {
+            let mut filter = FootnoteFilter::new(true).with_markup(markup);
+            let mut body = String::new();
+            pulldown_cmark::html::push_html(
+                &mut body,
+                Parser::new_ext(input, Options::ENABLE_FOOTNOTES).filter_map(|e| filter.apply(e)),
+            );
+            filter.retain();
+            pulldown_cmark::html::push_html(&mut body, filter.get_events());
+            body
+        };
+
+        let plain = render(NoteMarkup::Plain);
+        assert!(plain.contains(
+            r##"<sup class="footnote-reference" id="fr-a-1"><a href="#fn-a">[1]</a></sup>"##
+        ));
+        assert!(plain.contains(r##"<div class="footnote-definition" id="fn-a"><p>"##));
+        assert!(plain.contains("<a href=\"#fr-a-1\">↩</a></p>\n</div>"));
+        assert!(!plain.contains("epub:type"));
+
+        let aside = render(NoteMarkup::Aside);
+        assert!(aside.contains(r##"<a epub:type="noteref" href="#fn-a">[1]</a>"##));
+        assert!(aside.contains(
+            r##"<aside class="footnote-definition" id="fn-a" epub:type="footnote"><p>"##
+        ));
+        assert!(aside.contains("</p>\n</aside>"));
+
+        let div = render(NoteMarkup::Div);
+        assert!(div.contains(r##"<a epub:type="noteref" href="#fn-a">[1]</a>"##));
+        assert!(
+            div.contains(
+                r##"<div class="footnote-definition" id="fn-a" epub:type="footnote"><p>"##
+            )
+        );
+    }
+
     #[test]
     fn test_footnote_sorting() {
         let input = r#"First[^1] then[^2].
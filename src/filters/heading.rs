@@ -0,0 +1,211 @@
+use pulldown_cmark::{CowStr, Event, HeadingLevel, Tag, TagEnd};
+use std::collections::HashSet;
+
+/// A heading found in a chapter, with the anchor id it is rendered with.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Heading {
+    pub level: HeadingLevel,
+    pub id: String,
+    pub title: String,
+}
+
+/// The anchor ids used in a chapter, for generating unique ones the way mdbook's
+/// HTML renderer does. Other filters adding anchors to the chapter can use it to
+/// avoid clashes with the heading ids.
+#[derive(Debug, Default)]
+pub(crate) struct HeadingIds {
+    used: HashSet<String>,
+}
+
+impl HeadingIds {
+    /// Same as mdbook's `unique_id(&id_from_content(content))`: if the id made
+    /// from the content is taken, `-1`, `-2`, ... suffixes are tried until one
+    /// isn't.
+    pub(crate) fn unique_id(&mut self, content: &str) -> String {
+        let id = id_from_content(content);
+        if self.used.insert(id.clone()) {
+            return id;
+        }
+        let mut counter: u32 = 1;
+        loop {
+            let candidate = format!("{id}-{counter}");
+            if self.used.insert(candidate.clone()) {
+                return candidate;
+            }
+            counter += 1;
+        }
+    }
+
+    /// Record an id given explicitly, e.g. with a `{#custom-id}` heading attribute.
+    pub(crate) fn insert(&mut self, id: &str) {
+        self.used.insert(id.to_string());
+    }
+
+    pub(crate) fn contains(&self, id: &str) -> bool {
+        self.used.contains(id)
+    }
+}
+
+/// Gives every heading an anchor id the way mdbook's HTML renderer does, so links
+/// like `chapter.html#some-heading` keep working, and collects the headings of the
+/// chapter. Ids set with a `{#custom-id}` heading attribute are kept.
+#[derive(Debug, Default)]
+pub(crate) struct HeadingFilter<'a> {
+    /// Events of the heading being read, starting with its `Start` event.
+    in_heading: Vec<Event<'a>>,
+    ids: HeadingIds,
+    headings: Vec<Heading>,
+}
+
+impl<'a> HeadingFilter<'a> {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events of a heading are held back until its end, as the id depends on the
+    /// heading's text.
+    pub(crate) fn apply(&mut self, event: Event<'a>) -> Vec<Event<'a>> {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                self.in_heading.push(event);
+                Vec::new()
+            }
+            Event::End(TagEnd::Heading(_)) if !self.in_heading.is_empty() => {
+                let mut events = std::mem::take(&mut self.in_heading);
+                events.push(event);
+                self.finish_heading(&mut events);
+                events
+            }
+            _ if !self.in_heading.is_empty() => {
+                self.in_heading.push(event);
+                Vec::new()
+            }
+            _ => vec![event],
+        }
+    }
+
+    /// The headings found in the chapter and the anchor ids they use.
+    pub(crate) fn into_parts(self) -> (Vec<Heading>, HeadingIds) {
+        (self.headings, self.ids)
+    }
+
+    fn finish_heading(&mut self, events: &mut [Event<'a>]) {
+        let title: String = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Text(text) | Event::Code(text) => Some(text.as_ref()),
+                _ => None,
+            })
+            .collect();
+        let Some(Event::Start(Tag::Heading { level, id, .. })) = events.first_mut() else {
+            return;
+        };
+        let unique_id = match id {
+            Some(id) => {
+                self.ids.insert(id);
+                id.to_string()
+            }
+            None => self.ids.unique_id(&title),
+        };
+        *id = Some(CowStr::from(unique_id.clone()));
+        self.headings.push(Heading {
+            level: *level,
+            id: unique_id,
+            title,
+        });
+    }
+}
+
+/// Same as mdbook's `id_from_content`: keep alphanumerics, `_` and `-` in
+/// lowercase, replace whitespace with `-`, drop everything else.
+pub(crate) fn id_from_content(content: &str) -> String {
+    content
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|ch| {
+            if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                Some(ch)
+            } else if ch.is_whitespace() {
+                Some('-')
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::utils::create_new_pull_down_parser;
+    use pulldown_cmark::html;
+
+    fn render(markdown: &str) -> (String, Vec<Heading>) {
+        let mut filter = HeadingFilter::new();
+        let events = create_new_pull_down_parser(markdown, Config::default().markdown_options())
+            .flat_map(|event| filter.apply(event));
+        let mut body = String::new();
+        html::push_html(&mut body, events);
+        (body, filter.into_parts().0)
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let (body, headings) = render("# Getting Started\n\n## The `main` function!\n\nText\n");
+        assert!(body.contains("<h1 id=\"getting-started\">Getting Started</h1>"));
+        assert!(body.contains("<h2 id=\"the-main-function\">The <code>main</code> function!</h2>"));
+        assert_eq!(headings.len(), 2);
+        assert_eq!(headings[1].level, HeadingLevel::H2);
+        assert_eq!(headings[1].id, "the-main-function");
+        assert_eq!(headings[1].title, "The main function!");
+    }
+
+    #[test]
+    fn test_repeated_heading_ids() {
+        let (body, headings) = render("## Example\n\n## Example\n\n## Example\n");
+        let ids: Vec<_> = headings.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, vec!["example", "example-1", "example-2"]);
+        assert!(body.contains("<h2 id=\"example-1\">Example</h2>"));
+    }
+
+    #[test]
+    fn test_custom_heading_ids() {
+        let mut filter = HeadingFilter::new();
+        let events = create_new_pull_down_parser(
+            "## Setup {#install .big}\n\n## Install\n",
+            Config::default().markdown_options(),
+        )
+        .flat_map(|event| filter.apply(event));
+        let mut body = String::new();
+        html::push_html(&mut body, events);
+        assert!(body.contains("<h2 id=\"install\" class=\"big\">Setup</h2>"));
+        assert!(body.contains("<h2 id=\"install-1\">Install</h2>"));
+        let (headings, ids) = filter.into_parts();
+        assert!(ids.contains("install"));
+        assert!(!ids.contains("setup"));
+        assert_eq!(headings[0].title, "Setup");
+    }
+
+    #[test]
+    fn test_id_from_content() {
+        assert_eq!(id_from_content("Hello, World!"), "hello-world");
+        assert_eq!(
+            id_from_content("  snake_case and-dash "),
+            "snake_case-and-dash"
+        );
+        assert_eq!(id_from_content("Über Straße"), "über-straße");
+    }
+
+    #[test]
+    fn test_unique_id_skips_ids_in_use() {
+        let mut ids = HeadingIds::default();
+        assert_eq!(ids.unique_id("Example"), "example");
+        assert_eq!(ids.unique_id("Example-1"), "example-1");
+        assert_eq!(ids.unique_id("Example"), "example-2");
+        assert_eq!(ids.unique_id("Example"), "example-3");
+        ids.insert("custom");
+        assert_eq!(ids.unique_id("Custom"), "custom-1");
+    }
+}
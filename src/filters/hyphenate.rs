@@ -0,0 +1,207 @@
+use crate::errors::Error;
+use hyphenation::{Hyphenator, Language, Load, Standard};
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+/// Inserts soft hyphens into the long words of a chapter, so e-readers without
+/// hyphenation support of their own can still break them when justifying text.
+/// The soft hyphen is put in as a character, which is the same as `&shy;`.
+///
+/// Code, links, image descriptions and headings are left alone.
+pub(crate) struct HyphenationFilter<'d> {
+    dictionary: Option<&'d Standard>,
+    min_length: usize,
+    /// How deep we are in elements whose text isn't hyphenated.
+    skip_depth: usize,
+}
+
+impl<'d> HyphenationFilter<'d> {
+    /// A filter hyphenating words of at least `min_length` characters with the
+    /// given dictionary, or doing nothing without one.
+    pub(crate) fn new(dictionary: Option<&'d Standard>, min_length: usize) -> Self {
+        HyphenationFilter {
+            dictionary,
+            min_length,
+            skip_depth: 0,
+        }
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Event<'a> {
+        let Some(dictionary) = self.dictionary else {
+            return event;
+        };
+
+        match event {
+            Event::Start(ref tag) if is_skipped(tag) => {
+                self.skip_depth += 1;
+                event
+            }
+            Event::End(ref tag) if is_skipped_end(tag) => {
+                self.skip_depth = self.skip_depth.saturating_sub(1);
+                event
+            }
+            Event::Text(ref text) if self.skip_depth == 0 => {
+                Event::Text(CowStr::from(hyphenate(dictionary, text, self.min_length)))
+            }
+            _ => event,
+        }
+    }
+}
+
+fn is_skipped(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::CodeBlock(_) | Tag::Heading { .. } | Tag::Link { .. } | Tag::Image { .. }
+    )
+}
+
+fn is_skipped_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::CodeBlock | TagEnd::Heading(_) | TagEnd::Link | TagEnd::Image
+    )
+}
+
+/// Put soft hyphens into the words of `text` that have at least `min_length`
+/// letters.
+fn hyphenate(dictionary: &Standard, text: &str, min_length: usize) -> String {
+    let mut hyphenated = String::with_capacity(text.len() + text.len() / 4);
+    let mut rest = text;
+
+    while let Some(start) = rest.find(char::is_alphabetic) {
+        hyphenated.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let word = &rest[..end];
+
+        if word.chars().count() < min_length {
+            hyphenated.push_str(word);
+        } else {
+            let mut last = 0;
+            for i in dictionary.hyphenate(word).breaks {
+                hyphenated.push_str(&word[last..i]);
+                hyphenated.push(SOFT_HYPHEN);
+                last = i;
+            }
+            hyphenated.push_str(&word[last..]);
+        }
+        rest = &rest[end..];
+    }
+    hyphenated.push_str(rest);
+
+    hyphenated
+}
+
+/// Load the hyphenation dictionary for a language tag like `en` or `de-CH`, or
+/// `None` if there isn't one for the language.
+pub(crate) fn dictionary(language: &str) -> Result<Option<Standard>, Error> {
+    let Some(dictionary_language) = dictionary_language(language) else {
+        return Ok(None);
+    };
+    Standard::from_embedded(dictionary_language)
+        .map(Some)
+        .map_err(|e| Error::HyphenationDictionary {
+            language: language.to_string(),
+            reason: e.to_string(),
+        })
+}
+
+fn dictionary_language(language: &str) -> Option<Language> {
+    let tag = language.to_ascii_lowercase().replace('_', "-");
+    let language = match tag.as_str() {
+        "en" => Some(Language::EnglishUS),
+        "de" | "de-de" | "de-at" => Some(Language::German1996),
+        "de-ch" => Some(Language::GermanSwiss),
+        "el" => Some(Language::GreekMono),
+        "mn" => Some(Language::Mongolian),
+        "no" => Some(Language::NorwegianBokmal),
+        "sr" => Some(Language::SerbianCyrillic),
+        _ => Language::try_from_code(&tag),
+    };
+    // e.g. `fr-CA` falls back to `fr`
+    language.or_else(|| match tag.split_once('-') {
+        Some((primary, _)) => dictionary_language(primary),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{CodeBlockKind, LinkType};
+
+    fn english() -> Standard {
+        dictionary("en").unwrap().unwrap()
+    }
+
+    #[test]
+    fn test_dictionary_language() {
+        assert_eq!(dictionary_language("en"), Some(Language::EnglishUS));
+        assert_eq!(dictionary_language("en-GB"), Some(Language::EnglishGB));
+        assert_eq!(dictionary_language("en_AU"), Some(Language::EnglishUS));
+        assert_eq!(dictionary_language("de-AT"), Some(Language::German1996));
+        assert_eq!(dictionary_language("de-CH"), Some(Language::GermanSwiss));
+        assert_eq!(dictionary_language("fr-CA"), Some(Language::French));
+        assert_eq!(dictionary_language("ja"), None);
+        assert!(dictionary("ja").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_long_words_get_soft_hyphens() {
+        let dictionary = english();
+        assert_eq!(
+            hyphenate(&dictionary, "The hyphenation, obviously.", 8),
+            "The hy\u{AD}phen\u{AD}a\u{AD}tion, ob\u{AD}vi\u{AD}ously."
+        );
+        // short words are kept
+        assert_eq!(hyphenate(&dictionary, "obviously", 10), "obviously");
+    }
+
+    #[test]
+    fn test_skipped_elements() {
+        let dictionary = english();
+        let mut filter = HyphenationFilter::new(Some(&dictionary), 8);
+        let link = Tag::Link {
+            link_type: LinkType::Inline,
+            dest_url: CowStr::from("https://example.com"),
+            title: CowStr::from(""),
+            id: CowStr::from(""),
+        };
+        let events = vec![
+            Event::Start(link),
+            Event::Text(CowStr::from("hyphenation")),
+            Event::End(TagEnd::Link),
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(CowStr::from("")))),
+            Event::Text(CowStr::from("hyphenation")),
+            Event::End(TagEnd::CodeBlock),
+            Event::Code(CowStr::from("hyphenation")),
+            Event::Text(CowStr::from("hyphenation")),
+        ];
+        let text: Vec<String> = events
+            .into_iter()
+            .filter_map(|event| match filter.apply(event) {
+                Event::Text(text) | Event::Code(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            text,
+            vec![
+                "hyphenation",
+                "hyphenation",
+                "hyphenation",
+                "hy\u{AD}phen\u{AD}a\u{AD}tion"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_without_dictionary() {
+        let mut filter = HyphenationFilter::new(None, 1);
+        let event = Event::Text(CowStr::from("hyphenation"));
+        assert_eq!(filter.apply(event.clone()), event);
+    }
+}
@@ -0,0 +1,237 @@
+use math_core::{LatexToMathML, MathCoreConfig, MathDisplay};
+use mdbook_core::utils::escape_html;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use tracing::warn;
+
+/// Delimiters of TeX math in text, as used with mdbook's MathJax support.
+const DELIMITERS: [(&str, &str, MathDisplay); 3] = [
+    ("\\(", "\\)", MathDisplay::Inline),
+    ("\\[", "\\]", MathDisplay::Block),
+    ("$$", "$$", MathDisplay::Block),
+];
+
+/// Math found in the text, waiting for its closing delimiter.
+struct PendingMath {
+    open: &'static str,
+    close: &'static str,
+    display: MathDisplay,
+    tex: String,
+}
+
+/// Converts TeX math to MathML, as there is no JavaScript to typeset it in an
+/// EPUB. Handles pulldown-cmark's `$...$` and `$$...$$` math events, as well as
+/// MathJax's `\(...\)`, `\[...\]` and `$$...$$` in text. Without MathML (EPUB 2)
+/// the TeX source is shown instead.
+pub(crate) struct MathFilter {
+    converter: Option<LatexToMathML>,
+    in_code_block: bool,
+    pending: Option<PendingMath>,
+    has_mathml: bool,
+}
+
+impl MathFilter {
+    pub(crate) fn new(mathml: bool) -> Self {
+        let converter = mathml
+            .then(|| {
+                LatexToMathML::new(MathCoreConfig {
+                    xml_namespace: true,
+                    annotation: true,
+                    ..Default::default()
+                })
+                .ok()
+            })
+            .flatten();
+        MathFilter {
+            converter,
+            in_code_block: false,
+            pending: None,
+            has_mathml: false,
+        }
+    }
+
+    /// Whether any math was converted to MathML.
+    pub(crate) fn has_mathml(&self) -> bool {
+        self.has_mathml
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Vec<Event<'a>> {
+        match event {
+            Event::InlineMath(tex) => vec![self.render(&tex, MathDisplay::Inline)],
+            Event::DisplayMath(tex) => vec![self.render(&tex, MathDisplay::Block)],
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.in_code_block = true;
+                self.flush_with(event)
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.in_code_block = false;
+                vec![event]
+            }
+            Event::Text(text) if !self.in_code_block => self.convert_text(&text),
+            Event::SoftBreak | Event::HardBreak if self.pending.is_some() => {
+                if let Some(pending) = self.pending.as_mut() {
+                    pending.tex.push('\n');
+                }
+                Vec::new()
+            }
+            _ => self.flush_with(event),
+        }
+    }
+
+    /// Put back math that was never closed as text, ahead of `event`.
+    fn flush_with<'a>(&mut self, event: Event<'a>) -> Vec<Event<'a>> {
+        match self.pending.take() {
+            Some(pending) => vec![
+                Event::Text(CowStr::from(format!("{}{}", pending.open, pending.tex))),
+                event,
+            ],
+            None => vec![event],
+        }
+    }
+
+    fn convert_text<'a>(&mut self, text: &str) -> Vec<Event<'a>> {
+        let mut events = Vec::new();
+        let mut rest = text;
+
+        loop {
+            if let Some(mut pending) = self.pending.take() {
+                match rest.find(pending.close) {
+                    Some(end) => {
+                        pending.tex.push_str(&rest[..end]);
+                        events.push(self.render(&pending.tex, pending.display));
+                        rest = &rest[end + pending.close.len()..];
+                    }
+                    None => {
+                        pending.tex.push_str(rest);
+                        self.pending = Some(pending);
+                        return events;
+                    }
+                }
+            } else {
+                let opening = DELIMITERS
+                    .iter()
+                    .filter_map(|&(open, close, display)| {
+                        rest.find(open).map(|pos| (pos, open, close, display))
+                    })
+                    .min_by_key(|&(pos, ..)| pos);
+                let Some((pos, open, close, display)) = opening else {
+                    if !rest.is_empty() {
+                        events.push(Event::Text(CowStr::from(rest.to_string())));
+                    }
+                    return events;
+                };
+                if pos > 0 {
+                    events.push(Event::Text(CowStr::from(rest[..pos].to_string())));
+                }
+                self.pending = Some(PendingMath {
+                    open,
+                    close,
+                    display,
+                    tex: String::new(),
+                });
+                rest = &rest[pos + open.len()..];
+            }
+        }
+    }
+
+    /// The MathML for some TeX, or the TeX itself if it can't be converted.
+    fn render<'a>(&mut self, tex: &str, display: MathDisplay) -> Event<'a> {
+        if let Some(ref converter) = self.converter {
+            match converter.convert_with_local_state(tex.trim(), display) {
+                Ok(result) => {
+                    self.has_mathml = true;
+                    return Event::InlineHtml(CowStr::from(result.mathml));
+                }
+                Err(e) => warn!("Could not convert the math '{}' to MathML: {}", tex, e),
+            }
+        }
+        let class = match display {
+            MathDisplay::Inline => "math",
+            MathDisplay::Block => "math display",
+        };
+        Event::InlineHtml(CowStr::from(format!(
+            "<span class=\"{class}\">{}</span>",
+            escape_html(tex.trim())
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+    use crate::utils::create_new_pull_down_parser;
+    use pulldown_cmark::{TextMergeStream, html};
+
+    fn render(markdown: &str, mathml: bool) -> (String, bool) {
+        let config = Config {
+            math: Some(true),
+            ..Config::default()
+        };
+        let mut filter = MathFilter::new(mathml);
+        let events = TextMergeStream::new(create_new_pull_down_parser(
+            markdown,
+            config.markdown_options(),
+        ))
+        .flat_map(|event| filter.apply(event));
+        let mut out = String::new();
+        html::push_html(&mut out, events);
+        (out, filter.has_mathml())
+    }
+
+    #[test]
+    fn test_mathjax_delimiters() {
+        let (html, has_mathml) = render(
+            "Inline \\\\( x^2 \\\\) and\n\\\\[\n\\sqrt{2}\n\\\\]\n",
+            true,
+        );
+        assert!(has_mathml);
+        assert!(html.starts_with(
+            "<p>Inline <math xmlns=\"http://www.w3.org/1998/Math/MathML\"><semantics>"
+        ));
+        assert!(html.contains("<annotation encoding=\"application/x-tex\">x^2</annotation>"));
+        assert!(
+            html.contains("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">")
+        );
+        assert!(html.contains("<msqrt>"));
+        assert!(!html.contains("\\["));
+    }
+
+    #[test]
+    fn test_dollar_math() {
+        let (html, has_mathml) = render("Euler: $e^{i\\pi} + 1 = 0$\n\n$$\\frac{a}{b}$$\n", true);
+        assert!(has_mathml);
+        assert!(html.contains("<mfrac>"));
+        assert!(
+            html.contains(
+                "<annotation encoding=\"application/x-tex\">e^{i\\pi} + 1 = 0</annotation>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_code_is_left_alone() {
+        let (html, has_mathml) = render("`\\(x\\)`\n\n```\n\\(x\\)\n```\n", true);
+        assert!(!has_mathml);
+        assert_eq!(
+            html,
+            "<p><code>\\(x\\)</code></p>\n<pre><code>\\(x\\)\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_unclosed_math_is_kept() {
+        let (html, has_mathml) = render("costs \\\\( 5 *and* more\n", true);
+        assert!(!has_mathml);
+        assert_eq!(html, "<p>costs \\( 5 <em>and</em> more</p>\n");
+    }
+
+    #[test]
+    fn test_tex_source_without_mathml() {
+        let (html, has_mathml) = render("Inline \\\\( x < 2 \\\\) and $$y$$\n", false);
+        assert!(!has_mathml);
+        assert_eq!(
+            html,
+            "<p>Inline <span class=\"math\">x &lt; 2</span> and <span class=\"math display\">y</span></p>\n"
+        );
+    }
+}
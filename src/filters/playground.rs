@@ -0,0 +1,181 @@
+use crate::utils;
+use mdbook_core::config::RustEdition;
+use mdbook_core::utils::escape_html_attribute;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, Tag, TagEnd};
+use url::Url;
+
+const PLAYGROUND_URL: &str = "https://play.rust-lang.org/";
+
+/// Adds a link to the Rust Playground under runnable Rust code blocks, standing in
+/// for the "run" button of mdbook's HTML output.
+pub(crate) struct PlaygroundFilter {
+    /// The edition of the book, used for code blocks without an `edition20xx`
+    /// attribute.
+    edition: Option<&'static str>,
+    /// The edition and the code of the runnable code block being passed through.
+    current: Option<(Option<&'static str>, String)>,
+}
+
+impl PlaygroundFilter {
+    pub(crate) fn new(edition: Option<RustEdition>) -> Self {
+        PlaygroundFilter {
+            edition: edition.map(|edition| match edition {
+                RustEdition::E2015 => "2015",
+                RustEdition::E2018 => "2018",
+                RustEdition::E2021 => "2021",
+                _ => "2024",
+            }),
+            current: None,
+        }
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Vec<Event<'a>> {
+        match &event {
+            Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                self.current = self.runnable(info).map(|edition| (edition, String::new()));
+            }
+            Event::Text(text) => {
+                if let Some((_, code)) = &mut self.current {
+                    code.push_str(text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if let Some((edition, code)) = self.current.take() {
+                    let link = format!(
+                        "<p class=\"playground\"><a href=\"{}\">Run this code</a></p>\n",
+                        escape_html_attribute(playground_url(&code, edition).as_str())
+                    );
+                    return vec![event, Event::Html(CowStr::from(link))];
+                }
+            }
+            _ => {}
+        }
+        vec![event]
+    }
+
+    /// The edition to run a code block with, if it is runnable, i.e. Rust code
+    /// without the `ignore` or `noplayground` attributes.
+    fn runnable(&self, info: &str) -> Option<Option<&'static str>> {
+        let mut attributes = utils::code_block_attributes(info);
+        if attributes.next() != Some("rust") {
+            return None;
+        }
+        let mut edition = self.edition;
+        for attribute in attributes {
+            match attribute {
+                "ignore" | "noplayground" => return None,
+                "edition2015" => edition = Some("2015"),
+                "edition2018" => edition = Some("2018"),
+                "edition2021" => edition = Some("2021"),
+                "edition2024" => edition = Some("2024"),
+                _ => {}
+            }
+        }
+        Some(edition)
+    }
+}
+
+/// The Playground URL running the code, with its hidden lines and, like mdbook
+/// does, wrapped into a `main` function if it has none.
+fn playground_url(code: &str, edition: Option<&str>) -> Url {
+    let mut code: String = code.lines().map(unhide_line).collect();
+    if !code.contains("fn main") {
+        code = format!("#![allow(unused)]\nfn main() {{\n{code}}}\n");
+    }
+    let mut url = Url::parse(PLAYGROUND_URL).expect("the Playground URL is valid");
+    {
+        let mut query = url.query_pairs_mut();
+        query.append_pair("version", "stable");
+        query.append_pair("mode", "debug");
+        if let Some(edition) = edition {
+            query.append_pair("edition", edition);
+        }
+        query.append_pair("code", code.trim_end());
+    }
+    url
+}
+
+/// A line of the code, with the `#` marking hidden lines removed, and `##`
+/// unescaped to `#`.
+fn unhide_line(line: &str) -> String {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    let line = if let Some(rest) = trimmed.strip_prefix("##") {
+        format!("{indent}#{rest}")
+    } else if trimmed == "#" {
+        String::new()
+    } else if let Some(rest) = trimmed.strip_prefix("# ") {
+        format!("{indent}{rest}")
+    } else {
+        line.to_string()
+    };
+    line + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{Parser, html};
+
+    fn render(content: &str, edition: Option<RustEdition>) -> String {
+        let mut filter = PlaygroundFilter::new(edition);
+        let mut body = String::new();
+        html::push_html(
+            &mut body,
+            Parser::new(content).flat_map(|event| filter.apply(event)),
+        );
+        body
+    }
+
+    fn code(url: &Url) -> String {
+        url.query_pairs()
+            .find(|(key, _)| key == "code")
+            .map(|(_, code)| code.into_owned())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_runnable_code_blocks_get_a_link() {
+        let content = "```rust\nprintln!(\"Hi\");\n```\n\n```rust,ignore\nlet x;\n```\n\n```rust noplayground\nlet y;\n```\n\n```python\nprint(1)\n```\n\n```\nplain\n```\n";
+        let body = render(content, Some(RustEdition::E2021));
+        assert_eq!(body.matches("class=\"playground\"").count(), 1);
+        assert!(body.contains(
+            "println!(\"Hi\");\n</code></pre>\n<p class=\"playground\"><a href=\"https://play.rust-lang.org/?version=stable&amp;mode=debug&amp;edition=2021&amp;code="
+        ));
+    }
+
+    #[test]
+    fn test_edition_attribute() {
+        let filter = PlaygroundFilter::new(Some(RustEdition::E2021));
+        assert_eq!(filter.runnable("rust"), Some(Some("2021")));
+        assert_eq!(filter.runnable("rust,edition2018"), Some(Some("2018")));
+        assert_eq!(
+            filter.runnable("rust, should_panic, edition2024"),
+            Some(Some("2024"))
+        );
+        assert_eq!(filter.runnable("rust,no_run"), Some(Some("2021")));
+        assert_eq!(filter.runnable("rust,ignore"), None);
+        assert_eq!(filter.runnable("rustc"), None);
+        assert_eq!(PlaygroundFilter::new(None).runnable("rust"), Some(None));
+    }
+
+    #[test]
+    fn test_playground_code_includes_hidden_lines() {
+        let url = playground_url(
+            "# use std::fmt;\n#\nfn main() {\n    ## not hidden\n    # let x = 1;\n}\n",
+            None,
+        );
+        assert!(!url.as_str().contains("edition="));
+        assert_eq!(
+            code(&url),
+            "use std::fmt;\n\nfn main() {\n    # not hidden\n    let x = 1;\n}"
+        );
+    }
+
+    #[test]
+    fn test_playground_code_gets_a_main_function() {
+        let url = playground_url("let x = 1;\n", Some("2024"));
+        assert!(url.as_str().contains("edition=2024"));
+        assert_eq!(code(&url), "#![allow(unused)]\nfn main() {\nlet x = 1;\n}");
+    }
+}
@@ -0,0 +1,580 @@
+use crate::config::QuoteStyle;
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+
+const APOSTROPHE: char = '’';
+const NO_BREAK_SPACE: char = '\u{00A0}';
+const NARROW_NO_BREAK_SPACE: char = '\u{202F}';
+
+/// The quotation marks of a [`QuoteStyle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QuoteMarks {
+    double: (char, char),
+    single: (char, char),
+    /// Space put inside the double quotes, as in French `« mot »`.
+    inner_space: Option<char>,
+}
+
+impl QuoteStyle {
+    /// Pick the quote style for a language tag like `de` or `fr-CH`, falling
+    /// back to English ones.
+    pub(crate) fn for_language(language: &str) -> QuoteStyle {
+        match primary_language(language).as_str() {
+            "de" | "cs" | "sk" | "da" | "is" | "lt" | "sl" => QuoteStyle::German,
+            "fr" => QuoteStyle::French,
+            "ru" | "uk" | "be" | "pl" | "bg" => QuoteStyle::Russian,
+            "sv" | "fi" => QuoteStyle::Swedish,
+            _ => QuoteStyle::English,
+        }
+    }
+
+    fn marks(self) -> QuoteMarks {
+        let (double, single, inner_space) = match self {
+            QuoteStyle::English => (('“', '”'), ('‘', '’'), None),
+            QuoteStyle::German => (('„', '“'), ('‚', '‘'), None),
+            QuoteStyle::French => (('«', '»'), ('“', '”'), Some(NARROW_NO_BREAK_SPACE)),
+            QuoteStyle::Russian => (('«', '»'), ('„', '“'), None),
+            QuoteStyle::Swedish => (('”', '”'), ('’', '’'), None),
+        };
+        QuoteMarks {
+            double,
+            single,
+            inner_space,
+        }
+    }
+}
+
+/// Units kept on the same line as the number in front of them.
+const UNITS: &[&str] = &[
+    "%", "‰", "°", "°C", "°F", "K", "mm", "cm", "m", "km", "mg", "g", "kg", "t", "ml", "l", "s",
+    "ms", "µs", "ns", "min", "h", "Hz", "kHz", "MHz", "GHz", "B", "KB", "kB", "MB", "GB", "TB",
+    "KiB", "MiB", "GiB", "TiB", "px", "pt", "em", "V", "W", "kW", "€", "$", "£",
+];
+
+/// Typographic clean-up of the text of a chapter: curly quotes, dashes,
+/// ellipses and non-breaking spaces. Each rule can be turned on separately.
+/// Code, code blocks and inline HTML are left alone.
+///
+/// Quote conversion is from `mdbook/src/utils/mod.rs`, where this is a private
+/// struct. Quotes are tracked across the text events of a block, so a quote
+/// after emphasis or a link is still closed properly.
+pub struct TypographyFilter {
+    quotes: bool,
+    dashes: bool,
+    ellipses: bool,
+    spaces: bool,
+    /// Put non-breaking spaces in front of `;:!?` and inside `« »`, as French does.
+    french_spacing: bool,
+    convert_text: bool,
+    marks: QuoteMarks,
+    /// The last character seen in the current block, `None` at its start.
+    previous: Option<char>,
+    /// The straight quote behind `previous`, if it was turned into an opening quote.
+    after_opening: Option<char>,
+    double_open: bool,
+    single_open: bool,
+}
+
+impl TypographyFilter {
+    /// A filter converting straight quotes to curly ones if `quotes` is set, with
+    /// all other rules turned off.
+    pub(crate) fn new(quotes: bool) -> Self {
+        TypographyFilter {
+            quotes,
+            dashes: false,
+            ellipses: false,
+            spaces: false,
+            french_spacing: false,
+            convert_text: true,
+            marks: QuoteStyle::English.marks(),
+            previous: None,
+            after_opening: None,
+            double_open: false,
+            single_open: false,
+        }
+    }
+
+    /// Use the quotation marks of the given style.
+    pub(crate) fn with_style(mut self, style: QuoteStyle) -> Self {
+        self.marks = style.marks();
+        self
+    }
+
+    /// Turn `--` into an en dash and `---` into an em dash.
+    pub(crate) fn with_dashes(mut self, enabled: bool) -> Self {
+        self.dashes = enabled;
+        self
+    }
+
+    /// Turn `...` into an ellipsis.
+    pub(crate) fn with_ellipses(mut self, enabled: bool) -> Self {
+        self.ellipses = enabled;
+        self
+    }
+
+    /// Turn the spaces between numbers and units, and for French text the ones
+    /// in front of `;:!?`, into non-breaking spaces.
+    pub(crate) fn with_non_breaking_spaces(mut self, enabled: bool, language: &str) -> Self {
+        self.spaces = enabled;
+        self.french_spacing = enabled && primary_language(language) == "fr";
+        self
+    }
+
+    fn enabled(&self) -> bool {
+        self.quotes || self.dashes || self.ellipses || self.spaces
+    }
+
+    pub(crate) fn apply<'a>(&mut self, event: Event<'a>) -> Event<'a> {
+        if !self.enabled() {
+            return event;
+        }
+
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => {
+                self.convert_text = false;
+                self.reset();
+                event
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                self.convert_text = true;
+                event
+            }
+            Event::Start(ref tag) if is_block(tag) => {
+                self.reset();
+                event
+            }
+            Event::End(ref tag) if is_block_end(tag) => {
+                self.reset();
+                event
+            }
+            Event::Text(ref text) if self.convert_text => {
+                Event::Text(CowStr::from(self.convert(text)))
+            }
+            Event::Code(ref code) => {
+                self.previous = code.chars().last().or(self.previous);
+                self.after_opening = None;
+                event
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                self.previous = Some('\n');
+                self.after_opening = None;
+                event
+            }
+            _ => event,
+        }
+    }
+
+    /// Forget the quotes of the previous block.
+    fn reset(&mut self) {
+        self.previous = None;
+        self.after_opening = None;
+        self.double_open = false;
+        self.single_open = false;
+    }
+
+    fn convert(&mut self, original_text: &str) -> String {
+        let chars: Vec<char> = original_text.chars().collect();
+        let mut converted = String::with_capacity(original_text.len());
+        let mut i = 0;
+
+        while i < chars.len() {
+            let original_char = chars[i];
+            let next = chars.get(i + 1).copied();
+            let mut opening = false;
+            // the character the next one is looked at after
+            let mut seen = original_char;
+            match original_char {
+                '-' if self.dashes && next == Some('-') => {
+                    if chars.get(i + 2) == Some(&'-') {
+                        seen = '—';
+                        i += 2;
+                    } else {
+                        seen = '–';
+                        i += 1;
+                    }
+                    converted.push(seen);
+                }
+                '.' if self.ellipses && chars[i..].starts_with(&['.', '.', '.']) => {
+                    seen = '…';
+                    i += 2;
+                    converted.push(seen);
+                }
+                ' ' if self.spaces => {
+                    converted.push(self.space(&chars[i + 1..]));
+                }
+                '"' | '\'' if self.quotes => {
+                    opening = self.convert_quote(original_char, next, &mut converted);
+                }
+                _ => converted.push(original_char),
+            }
+
+            self.previous = Some(seen);
+            self.after_opening = opening.then_some(original_char);
+            i += 1;
+        }
+
+        converted
+    }
+
+    /// Push the curly version of a straight quote, returning whether it opens
+    /// a quotation.
+    fn convert_quote(&mut self, quote: char, next: Option<char>, converted: &mut String) -> bool {
+        let opens = self.opens_quote(quote, next);
+        match quote {
+            '"' if opens => {
+                converted.push(self.marks.double.0);
+                converted.extend(self.marks.inner_space);
+                self.double_open = true;
+            }
+            '"' => {
+                converted.extend(self.marks.inner_space);
+                converted.push(self.marks.double.1);
+                self.double_open = false;
+            }
+            _ if self.is_apostrophe(next) => {
+                converted.push(APOSTROPHE);
+                return false;
+            }
+            _ if opens => {
+                converted.push(self.marks.single.0);
+                self.single_open = true;
+            }
+            _ if self.single_open => {
+                converted.push(self.marks.single.1);
+                self.single_open = false;
+            }
+            _ => converted.push(APOSTROPHE),
+        }
+        opens
+    }
+
+    /// A quote opens when it starts the block or follows whitespace, an opening
+    /// bracket, a dash or an opening quote of the other kind, and isn't followed
+    /// by whitespace.
+    fn opens_quote(&self, quote: char, next: Option<char>) -> bool {
+        let after_opener = self.after_opening.is_some_and(|opened| opened != quote)
+            || match self.previous {
+                None => true,
+                Some(c) => c.is_whitespace() || matches!(c, '(' | '[' | '{' | '—' | '–'),
+            };
+        after_opener && !next.is_some_and(char::is_whitespace)
+    }
+
+    /// A `'` within a word, like in `don't`, or in front of a decade, like in
+    /// `'90s`, is an apostrophe.
+    fn is_apostrophe(&self, next: Option<char>) -> bool {
+        let previous_alphanumeric = self.previous.is_some_and(char::is_alphanumeric);
+        match next {
+            Some(c) if c.is_alphabetic() => previous_alphanumeric,
+            Some(c) if c.is_ascii_digit() => !previous_alphanumeric,
+            _ => false,
+        }
+    }
+
+    /// The space to put between the previous character and `after`.
+    fn space(&self, after: &[char]) -> char {
+        if self.french_spacing {
+            match (self.previous, after.first()) {
+                (_, Some(':')) => return NO_BREAK_SPACE,
+                (Some('«'), _) | (_, Some(';' | '!' | '?' | '»')) => {
+                    return NARROW_NO_BREAK_SPACE;
+                }
+                _ => {}
+            }
+        }
+        if self.previous.is_some_and(|c| c.is_ascii_digit()) {
+            let word: String = after
+                .iter()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, '.' | ',' | ';' | ':' | ')'))
+                .collect();
+            if UNITS.contains(&word.as_str()) {
+                return NO_BREAK_SPACE;
+            }
+        }
+        ' '
+    }
+}
+
+/// The language part of a language tag, e.g. `fr` for `fr-CA`.
+fn primary_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Tags starting a new block of text, where no quote is open yet.
+fn is_block(tag: &Tag) -> bool {
+    matches!(
+        tag,
+        Tag::Paragraph
+            | Tag::Heading { .. }
+            | Tag::BlockQuote(_)
+            | Tag::Item
+            | Tag::TableCell
+            | Tag::FootnoteDefinition(_)
+    )
+}
+
+fn is_block_end(tag: &TagEnd) -> bool {
+    matches!(
+        tag,
+        TagEnd::Paragraph
+            | TagEnd::Heading(_)
+            | TagEnd::BlockQuote(_)
+            | TagEnd::Item
+            | TagEnd::TableCell
+            | TagEnd::FootnoteDefinition
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pulldown_cmark::{CodeBlockKind, CowStr};
+
+    fn curly(text: &str) -> String {
+        TypographyFilter::new(true).convert(text)
+    }
+
+    fn curly_in(style: QuoteStyle, text: &str) -> String {
+        TypographyFilter::new(true).with_style(style).convert(text)
+    }
+
+    #[test]
+    fn test_basic_quote_conversion() {
+        let mut filter = TypographyFilter::new(true);
+
+        // Test single quotes
+        let input = Event::Text(CowStr::from("Here's a 'quote'"));
+        if let Event::Text(result) = filter.apply(input) {
+            println!("{}", result.as_ref());
+            assert_eq!(result.as_ref(), "Here’s a ‘quote’");
+        } else {
+            panic!("Expected Text event");
+        }
+
+        // Test double quotes
+        let input = Event::Text(CowStr::from(r#"He said "hello""#));
+        if let Event::Text(result) = filter.apply(input) {
+            assert_eq!(result.as_ref(), r#"He said “hello”"#);
+        } else {
+            panic!("Expected Text event");
+        }
+    }
+
+    #[test]
+    fn test_disabled_filter() {
+        let mut filter = TypographyFilter::new(false);
+        let input = Event::Text(CowStr::from(r#"'test' and "test""#));
+        if let Event::Text(result) = filter.apply(input) {
+            assert_eq!(result.as_ref(), r#"'test' and "test""#);
+        } else {
+            panic!("Expected Text event");
+        }
+    }
+
+    #[test]
+    fn test_code_block_handling() {
+        let mut filter = TypographyFilter::new(true);
+
+        // Start code block
+        filter.apply(Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(
+            CowStr::from("rust"),
+        ))));
+
+        // Text within code block shouldn't be converted
+        let code_text = Event::Text(CowStr::from(r#"let s = "string";"#));
+        if let Event::Text(result) = filter.apply(code_text) {
+            assert_eq!(result.as_ref(), r#"let s = "string";"#);
+        } else {
+            panic!("Expected Text event");
+        }
+
+        // End code block
+        filter.apply(Event::End(TagEnd::CodeBlock));
+
+        // Text after code block should be converted again
+        let after_code = Event::Text(CowStr::from(r#""test""#));
+        if let Event::Text(result) = filter.apply(after_code) {
+            assert_eq!(result.as_ref(), r#"“test”"#);
+        } else {
+            panic!("Expected Text event");
+        }
+    }
+
+    #[test]
+    fn test_convert_quotes_to_curly() {
+        // Test various quote patterns
+        assert_eq!(curly("'start' mid 'end'"), "‘start’ mid ‘end’");
+
+        assert_eq!(
+            curly(r#""Hello" he's "saying""#),
+            r#"“Hello” he’s “saying”"#
+        );
+    }
+
+    #[test]
+    fn test_whitespace_handling() {
+        assert_eq!(curly("word'word'word"), "word’word’word");
+
+        assert_eq!(curly("word 'word' word"), "word ‘word’ word");
+
+        // Test with various whitespace characters
+        assert_eq!(
+            curly("\t'tab'\n'newline'\r'return'"),
+            "\t‘tab’\n‘newline’\r‘return’"
+        );
+    }
+
+    #[test]
+    fn test_mixed_quotes() {
+        assert_eq!(
+            curly(r#"'single' and "double" quotes"#),
+            r#"‘single’ and “double” quotes"#
+        );
+    }
+
+    #[test]
+    fn test_empty_and_whitespace() {
+        assert_eq!(curly(""), "");
+        assert_eq!(curly(" "), " ");
+        assert_eq!(curly("''"), "‘’");
+        assert_eq!(curly(r#""""#), r#"“”"#);
+    }
+
+    #[test]
+    fn test_opening_after_punctuation() {
+        assert_eq!(curly(r#"(see "here")"#), "(see “here”)");
+        assert_eq!(curly(r#"yes—"no""#), "yes—“no”");
+        assert_eq!(curly(r#"["a", 'b']"#), "[“a”, ‘b’]");
+    }
+
+    #[test]
+    fn test_apostrophes() {
+        assert_eq!(curly("don't, won't"), "don’t, won’t");
+        assert_eq!(curly("the '90s and the 80's"), "the ’90s and the 80’s");
+        assert_eq!(curly("the students' books"), "the students’ books");
+        assert_eq!(
+            curly_in(QuoteStyle::German, "Peter's 'Buch' aus den '90ern"),
+            "Peter’s ‚Buch‘ aus den ’90ern"
+        );
+    }
+
+    #[test]
+    fn test_nested_quotes() {
+        assert_eq!(
+            curly(r#""She said 'no'," he wrote"#),
+            "“She said ‘no’,” he wrote"
+        );
+        assert_eq!(
+            curly_in(QuoteStyle::Russian, r#""Он сказал 'нет'""#),
+            "«Он сказал „нет“»"
+        );
+    }
+
+    #[test]
+    fn test_locale_styles() {
+        assert_eq!(
+            curly_in(QuoteStyle::German, r#"Er sagte "Hallo""#),
+            "Er sagte „Hallo“"
+        );
+        assert_eq!(
+            curly_in(QuoteStyle::French, r#"Il dit "bonjour""#),
+            "Il dit «\u{202F}bonjour\u{202F}»"
+        );
+        assert_eq!(
+            curly_in(QuoteStyle::Swedish, r#"Han sa "hej" och 'då'"#),
+            "Han sa ”hej” och ’då’"
+        );
+    }
+
+    #[test]
+    fn test_quote_style_for_language() {
+        assert_eq!(QuoteStyle::for_language("en"), QuoteStyle::English);
+        assert_eq!(QuoteStyle::for_language("de-AT"), QuoteStyle::German);
+        assert_eq!(QuoteStyle::for_language("fr_CA"), QuoteStyle::French);
+        assert_eq!(QuoteStyle::for_language("RU"), QuoteStyle::Russian);
+        assert_eq!(QuoteStyle::for_language("sv"), QuoteStyle::Swedish);
+        assert_eq!(QuoteStyle::for_language("ja"), QuoteStyle::English);
+    }
+
+    #[test]
+    fn test_state_across_text_events() {
+        let mut filter = TypographyFilter::new(true);
+        let events = vec![
+            Event::Start(Tag::Paragraph),
+            Event::Text(CowStr::from("He said \"")),
+            Event::Start(Tag::Emphasis),
+            Event::Text(CowStr::from("hi")),
+            Event::End(TagEnd::Emphasis),
+            Event::Text(CowStr::from("\" and left")),
+            Event::End(TagEnd::Paragraph),
+            Event::Start(Tag::Paragraph),
+            Event::Code(CowStr::from("x")),
+            Event::Text(CowStr::from("'s value")),
+            Event::End(TagEnd::Paragraph),
+        ];
+        let text: Vec<String> = events
+            .into_iter()
+            .filter_map(|event| match filter.apply(event) {
+                Event::Text(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, vec!["He said “", "hi", "” and left", "’s value"]);
+    }
+
+    #[test]
+    fn test_dashes_and_ellipses() {
+        let mut filter = TypographyFilter::new(false)
+            .with_dashes(true)
+            .with_ellipses(true);
+        assert_eq!(
+            filter.convert("pages 10--20 --- or so... she said"),
+            "pages 10–20 — or so… she said"
+        );
+        assert_eq!(filter.convert("a - b .. c"), "a - b .. c");
+
+        // quotes after an em dash open
+        let mut filter = TypographyFilter::new(true).with_dashes(true);
+        assert_eq!(filter.convert(r#"yes---"no""#), "yes—“no”");
+    }
+
+    #[test]
+    fn test_rules_can_be_turned_off() {
+        let mut filter = TypographyFilter::new(true);
+        assert_eq!(filter.convert(r#""a" -- b... 10 kg"#), "“a” -- b... 10 kg");
+        let mut filter = TypographyFilter::new(false).with_dashes(true);
+        assert_eq!(filter.convert(r#""a" -- b"#), r#""a" – b"#);
+    }
+
+    #[test]
+    fn test_non_breaking_spaces() {
+        let mut filter = TypographyFilter::new(false).with_non_breaking_spaces(true, "en");
+        assert_eq!(
+            filter.convert("It weighs 10 kg, costs 5 € and is 3 items; really ?"),
+            "It weighs 10\u{A0}kg, costs 5\u{A0}€ and is 3 items; really ?"
+        );
+
+        let mut filter = TypographyFilter::new(false).with_non_breaking_spaces(true, "fr-CA");
+        assert_eq!(
+            filter.convert("Vraiment ? Oui : « 50 % » ; bien !"),
+            "Vraiment\u{202F}? Oui\u{A0}: «\u{202F}50\u{A0}%\u{202F}»\u{202F}; bien\u{202F}!"
+        );
+    }
+
+    #[test]
+    fn test_code_and_html_are_left_alone() {
+        let mut filter = TypographyFilter::new(true)
+            .with_dashes(true)
+            .with_ellipses(true);
+        for event in [
+            Event::Code(CowStr::from("a -- b...")),
+            Event::InlineHtml(CowStr::from("<span title=\"a--b\">")),
+            Event::Html(CowStr::from("<!-- \"x\" -->")),
+        ] {
+            assert_eq!(filter.apply(event.clone()), event);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::Error;
+use epub_builder::ReferenceType;
+use serde::Deserialize;
+use std::path::PathBuf;
+use tracing::debug;
+
+const TOML_DELIMITER: &str = "+++";
+
+/// EPUB-specific chapter properties, taken from an optional front-matter block
+/// (TOML between `+++` lines) at the very top of the chapter's Markdown.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct FrontMatter {
+    /// The role this chapter plays in the book.
+    #[serde(rename = "type")]
+    pub chapter_type: Option<ChapterType>,
+    /// Language of the chapter, when it differs from `book.language`.
+    #[serde(alias = "lang")]
+    pub language: Option<String>,
+    /// Leave the chapter out of the EPUB.
+    pub exclude: bool,
+    /// The template to render this chapter with (relative to the book root)
+    /// instead of the `index-template`.
+    pub template: Option<PathBuf>,
+}
+
+/// Semantic type of a chapter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ChapterType {
+    #[serde(alias = "front-matter")]
+    Frontmatter,
+    #[serde(alias = "back-matter")]
+    Backmatter,
+    Appendix,
+    Glossary,
+    Index,
+    Bibliography,
+    Colophon,
+}
+
+impl ChapterType {
+    /// The EPUB 3 structural semantics value (`epub:type`) of the chapter.
+    pub(crate) fn epub_type(&self) -> &'static str {
+        match self {
+            ChapterType::Frontmatter => "frontmatter",
+            ChapterType::Backmatter => "backmatter",
+            ChapterType::Appendix => "appendix",
+            ChapterType::Glossary => "glossary",
+            ChapterType::Index => "index",
+            ChapterType::Bibliography => "bibliography",
+            ChapterType::Colophon => "colophon",
+        }
+    }
+
+    /// Whether the chapter belongs to the back matter of the book.
+    pub(crate) fn is_back_matter(&self) -> bool {
+        !matches!(self, ChapterType::Frontmatter)
+    }
+
+    /// The guide/landmark reference type, for the types `epub-builder` knows about.
+    pub(crate) fn reference_type(&self) -> Option<ReferenceType> {
+        match self {
+            ChapterType::Glossary => Some(ReferenceType::Glossary),
+            ChapterType::Index => Some(ReferenceType::Index),
+            ChapterType::Bibliography => Some(ReferenceType::Bibliography),
+            ChapterType::Colophon => Some(ReferenceType::Colophon),
+            _ => None,
+        }
+    }
+
+    /// The landmark listed for the chapter, for the types that have one.
+    pub(crate) fn landmark(&self) -> Option<&'static str> {
+        self.reference_type().map(|_| self.epub_type())
+    }
+}
+
+impl FrontMatter {
+    /// Split the front-matter block off the chapter content, returning the parsed
+    /// properties and the remaining Markdown. Content without a front-matter block
+    /// is returned untouched along with the default properties.
+    pub(crate) fn split(content: &str) -> Result<(FrontMatter, &str), Error> {
+        if let Some((block, rest)) = extract_block(content, TOML_DELIMITER) {
+            let front_matter = toml::from_str(block)?;
+            debug!("Found TOML front matter: {:?}", front_matter);
+            return Ok((front_matter, rest));
+        }
+
+        Ok((FrontMatter::default(), content))
+    }
+}
+
+/// Find a block enclosed in `delimiter` lines at the start of the content and
+/// return it together with the content following the closing delimiter.
+fn extract_block<'a>(content: &'a str, delimiter: &str) -> Option<(&'a str, &'a str)> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content.split_inclusive('\n');
+    let opening = lines.next()?;
+    if opening.trim_end() != delimiter {
+        return None;
+    }
+
+    let start = opening.len();
+    let mut end = start;
+    for line in lines {
+        if line.trim_end() == delimiter {
+            return Some((&content[start..end], &content[end + line.len()..]));
+        }
+        end += line.len();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_front_matter() {
+        let content = "# Chapter 1\n\nSome text\n";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter, FrontMatter::default());
+        assert_eq!(rest, content);
+    }
+
+    #[test]
+    fn test_toml_front_matter() {
+        let content = "+++\ntype = \"appendix\"\nlanguage = \"de\"\n+++\n# Anhang\n";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter.chapter_type, Some(ChapterType::Appendix));
+        assert_eq!(front_matter.language.as_deref(), Some("de"));
+        assert!(!front_matter.exclude);
+        assert_eq!(rest, "# Anhang\n");
+    }
+
+    #[test]
+    fn test_toml_front_matter_keys() {
+        let content = "+++\ntype = \"colophon\"\nlang = \"fr\"\ntemplate = \"colophon.hbs\"\nexclude = true\n+++\n\n# Colophon";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter.chapter_type, Some(ChapterType::Colophon));
+        assert_eq!(front_matter.language.as_deref(), Some("fr"));
+        assert_eq!(front_matter.template, Some(PathBuf::from("colophon.hbs")));
+        assert!(front_matter.exclude);
+        assert_eq!(rest, "\n# Colophon");
+    }
+
+    #[test]
+    fn test_thematic_break_is_not_front_matter() {
+        let content = "---\nHeading\n---\n\nText";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter, FrontMatter::default());
+        assert_eq!(rest, content);
+
+        // a setext heading that looks like a YAML mapping is kept as well
+        let content = "---\nAuthor: Jane\n---\n\nText";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter, FrontMatter::default());
+        assert_eq!(rest, content);
+    }
+
+    #[test]
+    fn test_unclosed_block_is_not_front_matter() {
+        let content = "+++\ntype = \"appendix\"\n# Chapter";
+        let (front_matter, rest) = FrontMatter::split(content).unwrap();
+        assert_eq!(front_matter, FrontMatter::default());
+        assert_eq!(rest, content);
+    }
+
+    #[test]
+    fn test_invalid_front_matter_type() {
+        let content = "+++\ntype = \"prologue\"\n+++\n# Chapter";
+        assert!(FrontMatter::split(content).is_err());
+    }
+
+    #[test]
+    fn test_chapter_type_mapping() {
+        assert_eq!(ChapterType::Backmatter.epub_type(), "backmatter");
+        assert_eq!(
+            ChapterType::Glossary.reference_type(),
+            Some(ReferenceType::Glossary)
+        );
+        assert_eq!(ChapterType::Appendix.reference_type(), None);
+        assert_eq!(ChapterType::Index.landmark(), Some("index"));
+        assert_eq!(ChapterType::Appendix.landmark(), None);
+        assert!(ChapterType::Appendix.is_back_matter());
+        assert!(!ChapterType::Frontmatter.is_back_matter());
+    }
+}
@@ -1,31 +1,92 @@
-use crate::config::Config;
+use crate::config::{COVER_PAGE_TEMPLATE, Config, EndnoteNumbering, QuoteStyle};
+use crate::cover::{
+    COVER_PAGE_PATH, CoverImage, GENERATED_COVER_PATH, GENERATED_COVER_SIZE, SUBTITLE_LINE_LENGTH,
+    TITLE_LINE_LENGTH, image_size, wrap_text,
+};
+use crate::diagram::{CodeRenderer, DIAGRAMS_DIR};
+use crate::filters::admonition::{self, AdmonitionFilter};
 use crate::filters::asset_link::AssetRemoteLinkFilter;
-use crate::filters::footnote::FootnoteFilter;
-use crate::filters::quote_converter::QuoteConverterFilter;
+use crate::filters::chapter_link::{AnchorLink, ChapterLinkFilter};
+use crate::filters::code_block::CodeBlockFilter;
+use crate::filters::diagram::DiagramFilter;
+use crate::filters::footnote::{FootnoteFilter, NoteLinks, NoteMarkup};
+use crate::filters::heading::{Heading, HeadingFilter, HeadingIds, id_from_content};
+use crate::filters::hyphenate::{self, HyphenationFilter};
+use crate::filters::math::MathFilter;
+use crate::filters::playground::PlaygroundFilter;
+use crate::filters::typography::TypographyFilter;
+use crate::front_matter::{ChapterType, FrontMatter};
+use crate::package::{Landmark, PackageAdjustments};
 use crate::resources::asset::Asset;
 use crate::resources::resource::{self};
 use crate::resources::retrieve::{ContentRetriever, ResourceHandler};
+use crate::selection::{ChapterSelection, SelectedChapter};
+use crate::theme::{self, ChapterTemplates};
+use crate::toc::{INLINE_TOC_PATH, TocEntry, heading_toc_elements, render_toc_list};
 use crate::validation::validate_config_epub_version;
 use crate::{DEFAULT_CSS, file_io};
 use crate::{Error, utils};
-use epub_builder::{EpubBuilder, EpubContent, ZipLibrary};
-use handlebars::{Handlebars, RenderError, RenderErrorReason};
-use mdbook_core::book::{BookItem, Chapter};
+use base64::prelude::*;
+use epub_builder::{EpubBuilder, EpubContent, ReferenceType, ZipLibrary};
+use handlebars::Handlebars;
+use hyphenation::Standard;
+use mdbook_core::book::{Chapter, SectionNumber};
+use mdbook_core::utils::{escape_html, escape_html_attribute};
 use mdbook_renderer::RenderContext;
-use pulldown_cmark::html;
+use pulldown_cmark::{TextMergeStream, html};
 use serde_json::json;
 use std::collections::HashSet;
+use std::fmt::Write as _;
 use std::path::Path;
 use std::{
     collections::HashMap,
     fmt::{self, Debug, Formatter},
     fs::File,
     io::{Read, Write},
-    iter,
     path::PathBuf,
 };
 use tracing::{debug, error, info, trace, warn};
 
+/// Title `epub-builder` gives to the navigation document.
+const TOC_TITLE: &str = "Table Of Contents";
+const TITLE_PAGE_PATH: &str = "title_page.html";
+const COPYRIGHT_PAGE_PATH: &str = "copyright.html";
+/// Text of the placeholder pages for draft chapters.
+const DRAFT_PLACEHOLDER: &str = "This chapter is not written yet.";
+const NOTES_PATH: &str = "notes.html";
+
+/// A chapter rendered into XHTML, along with the properties from its front matter.
+struct RenderedChapter {
+    content: String,
+    front_matter: FrontMatter,
+    headings: Vec<Heading>,
+    /// Anchor ids of the chapter's headings.
+    ids: HeadingIds,
+    /// Links to anchors in other chapters.
+    anchor_links: Vec<AnchorLink>,
+    /// The chapter's footnotes as XHTML, when they are collected as endnotes.
+    notes: String,
+    /// Whether the chapter has MathML in it.
+    mathml: bool,
+}
+
+/// Where a chapter sits in the book, for the context of the chapter template.
+#[derive(Debug, Default)]
+struct ChapterPosition<'c> {
+    number: Option<&'c SectionNumber>,
+    previous: Option<&'c Chapter>,
+    next: Option<&'c Chapter>,
+}
+
+/// A rendered chapter with everything needed to add it to the EPUB.
+struct PreparedChapter {
+    path: String,
+    title: String,
+    level: i32,
+    is_start: bool,
+    rendered: RenderedChapter,
+}
+
 /// The actual EPUB book renderer.
 pub struct Generator<'a> {
     ctx: &'a RenderContext,
@@ -34,6 +95,16 @@ pub struct Generator<'a> {
     hbs: Handlebars<'a>,
     assets: HashMap<String, Asset>,
     handler: Box<dyn ContentRetriever>,
+    selection: ChapterSelection,
+    chapter_templates: ChapterTemplates,
+    package: PackageAdjustments,
+    cover_image: Option<CoverImage>,
+    /// Number of footnotes in the chapters rendered so far.
+    note_count: usize,
+    /// Hyphenation dictionaries by language, `None` for languages without one.
+    hyphenators: HashMap<String, Option<Standard>>,
+    /// The rendered code block images added to the book so far.
+    diagrams: HashSet<String>,
 }
 
 impl<'a> Generator<'a> {
@@ -49,15 +120,38 @@ impl<'a> Generator<'a> {
         let config = Config::from_render_context(ctx)?;
 
         let epub_version = validate_config_epub_version(&config)?;
+        let selection = ChapterSelection::from_config(&config)?;
 
         let mut builder = EpubBuilder::new(ZipLibrary::new()?)?;
         if let Some(version) = epub_version {
             builder.epub_version(version);
         }
 
+        let chapter_templates = ChapterTemplates::new(&config.chapter_templates)?;
         let mut hbs = Handlebars::new();
-        hbs.register_template_string("index", config.template()?)
-            .map_err(|_| Error::TemplateParse)?;
+        theme::register_helpers(&mut hbs);
+        if let Some(ref theme_dir) = config.theme_dir {
+            theme::register_partials(&mut hbs, theme_dir)?;
+        }
+        let templates = [
+            ("index", config.template()?, &config.index_template),
+            (
+                "title_page",
+                config.title_page_template()?,
+                &config.title_page_template,
+            ),
+            (
+                "copyright_page",
+                config.copyright_page_template()?,
+                &config.copyright_page_template,
+            ),
+            ("cover", config.cover_template()?, &config.cover_template),
+            ("cover_page", COVER_PAGE_TEMPLATE.to_string(), &None),
+        ];
+        for (name, template, template_file) in templates {
+            hbs.register_template_string(name, template)
+                .map_err(|e| Error::template_parse(template_label(name, template_file), e))?;
+        }
 
         Ok(Generator {
             builder,
@@ -66,6 +160,13 @@ impl<'a> Generator<'a> {
             hbs,
             assets: HashMap::new(),
             handler,
+            selection,
+            chapter_templates,
+            package: PackageAdjustments::default(),
+            cover_image: None,
+            note_count: 0,
+            hyphenators: HashMap::new(),
+            diagrams: HashSet::new(),
         })
     }
 
@@ -90,6 +191,10 @@ impl<'a> Generator<'a> {
 
         self.builder.metadata("generator", env!("CARGO_PKG_NAME"))?;
 
+        if let Some(rights) = self.config.rights.clone() {
+            self.builder.metadata("license", rights)?;
+        }
+
         if let Some(lang) = self.ctx.config.book.language.clone() {
             self.builder.metadata("lang", lang)?;
         } else {
@@ -99,19 +204,22 @@ impl<'a> Generator<'a> {
         Ok(())
     }
 
-    pub fn generate<W: Write>(mut self, writer: W) -> Result<(), Error> {
+    pub fn generate<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
         info!("Generating the EPUB book");
 
         self.populate_metadata()?;
         self.find_assets()?;
+        self.add_cover_image()?;
         self.generate_chapters()?;
 
-        self.add_cover_image()?;
         self.embed_stylesheets()?;
         self.additional_assets()?;
         self.additional_resources()?;
         info!("8. final generation ==");
-        self.builder.generate(writer)?;
+        let mut epub = Vec::new();
+        self.builder.generate(&mut epub)?;
+        let epub = self.package.apply(epub)?;
+        file_io(writer.write_all(&epub), "write", "EPUB book")?;
         info!("Generating the EPUB book - DONE !");
         Ok(())
     }
@@ -122,7 +230,7 @@ impl<'a> Generator<'a> {
         info!("2.1 Start find_assets()...");
         // resources::find can emit very unclear error based on internal MD content,
         // so let's give a tip to user in error message
-        let assets = resource::find(self.ctx).map_err(|e| {
+        let assets = resource::find(self.ctx, &self.selection, self.config.markdown_options()).map_err(|e| {
             let error = String::from(
                 "Failed finding/fetch resource taken from content? Look up content for possible error...",
             );
@@ -135,43 +243,436 @@ impl<'a> Generator<'a> {
     }
 
     fn generate_chapters(&mut self) -> Result<(), Error> {
-        info!("3.1 Generate chapters == ");
+        info!("4.1 Generate chapters == ");
+
+        self.add_landmark(Landmark::new("toc", "nav.xhtml#toc", TOC_TITLE));
+
+        // render the main chapters + sub-chapters, leaving out the ones not selected for the EPUB
+        let selected = self.selection.chapters(&self.ctx.book);
+        let placeholders = self.draft_placeholders(&selected);
+        let chapters: Vec<SelectedChapter> = selected
+            .iter()
+            .zip(&placeholders)
+            .map(|(selected, placeholder)| match placeholder {
+                Some(placeholder) => SelectedChapter {
+                    chapter: placeholder,
+                    ..selected.clone()
+                },
+                None => selected.clone(),
+            })
+            .collect();
+        let start = self.start_of_content(&chapters);
+        // chapters making it into the EPUB, for the previous/next links of the templates
+        let readable: Vec<&Chapter> = chapters
+            .iter()
+            .map(|selected| selected.chapter)
+            .filter(|ch| is_readable(ch))
+            .collect();
+        let mut prepared = Vec::with_capacity(chapters.len());
+        let mut failures = Vec::new();
+        for (idx, selected) in chapters.iter().enumerate() {
+            trace!("Rendering chapter \"{}\"", selected.chapter);
+            let here = readable
+                .iter()
+                .position(|ch| std::ptr::eq(*ch, selected.chapter));
+            let position = ChapterPosition {
+                number: selected.number.as_ref(),
+                previous: here.and_then(|i| i.checked_sub(1)).map(|i| readable[i]),
+                next: here.and_then(|i| readable.get(i + 1)).copied(),
+            };
+            match self.prepare_chapter(selected, &position, start == Some(idx)) {
+                Ok(Some(chapter)) => prepared.push(chapter),
+                Ok(None) => {}
+                Err(error) => {
+                    error!("{}", error);
+                    failures.push(error);
+                }
+            }
+        }
+        if !failures.is_empty() {
+            return Err(Error::ChapterFailures(failures));
+        }
+
+        check_anchor_links(&prepared);
+
+        if self.config.cover_page {
+            self.add_cover_page()?;
+        }
+        if self.config.title_page {
+            self.add_title_page()?;
+        }
+        if self.config.copyright_page {
+            self.add_generated_page(
+                "copyright_page",
+                COPYRIGHT_PAGE_PATH,
+                "Copyright",
+                ReferenceType::Copyright,
+                "copyright-page",
+            )?;
+        }
+        if self.config.inline_toc {
+            self.add_inline_toc(&prepared)?;
+        }
 
+        let notes = self.endnotes(&prepared);
         let mut added_count = 0;
-        // add the main chapters + sub-chapters
-        for (idx, item) in self.ctx.book.iter().enumerate() {
-            let is_first = idx == 0;
-            if let BookItem::Chapter(ref ch) = *item {
-                trace!("Adding chapter \"{}\"", ch);
-                self.add_chapter(ch, Some(is_first))?;
-                added_count += 1;
+        for chapter in prepared {
+            self.add_chapter(chapter)?;
+            added_count += 1;
+        }
+        if let Some(notes) = notes {
+            self.add_endnotes(notes)?;
+        }
+        info!("4.2 Generate [{}] chapters == ", added_count);
+        Ok(())
+    }
+
+    /// The endnotes of the chapters, grouped under the chapter titles, or nothing if
+    /// `endnotes` is off or there are no notes.
+    fn endnotes(&self, chapters: &[PreparedChapter]) -> Option<String> {
+        if !self.config.endnotes {
+            return None;
+        }
+        let mut notes = String::new();
+        for chapter in chapters.iter().filter(|ch| !ch.rendered.notes.is_empty()) {
+            write!(
+                notes,
+                "<div class=\"endnotes-chapter\">\n<h2><a href=\"{}\">{}</a></h2>\n{}</div>\n",
+                escape_html_attribute(&chapter.path.replace('\\', "/")),
+                escape_html(&chapter.title),
+                chapter.rendered.notes
+            )
+            .unwrap();
+        }
+        (!notes.is_empty()).then_some(notes)
+    }
+
+    /// Add the notes chapter with the endnotes of all chapters as back matter at the
+    /// end of the book.
+    fn add_endnotes(&mut self, notes: String) -> Result<(), Error> {
+        info!("Adding the endnotes chapter");
+        let title = self.config.endnotes_title.clone();
+        let epub_version_3 = self.config.epub_version == Some(3);
+        let body = if epub_version_3 {
+            format!(
+                "<h1>{}</h1>\n<div class=\"endnotes\" epub:type=\"endnotes\">\n{}</div>\n",
+                escape_html(&title),
+                notes
+            )
+        } else {
+            format!(
+                "<h1>{}</h1>\n<div class=\"endnotes\">\n{}</div>\n",
+                escape_html(&title),
+                notes
+            )
+        };
+        let mut ctx = self.page_context(
+            &title,
+            body,
+            "",
+            json!({"name": title, "path": NOTES_PATH, "parent_names": [], "depth": 0}),
+        );
+        ctx["classes"] = json!("backmatter endnotes");
+        let content = self
+            .hbs
+            .render("index", &ctx)
+            .map_err(|e| self.render_error(&title, "index", e))?;
+        // footnotes with math in them
+        if epub_version_3 && notes.contains("<math ") {
+            self.package
+                .manifest_properties
+                .push((NOTES_PATH.to_string(), "mathml"));
+        }
+        self.add_matter_start(ChapterType::Backmatter, NOTES_PATH, &title);
+        self.builder.add_content(
+            EpubContent::new(NOTES_PATH, content.as_bytes())
+                .title(title)
+                .reftype(ReferenceType::Notes),
+        )?;
+        Ok(())
+    }
+
+    /// Placeholder pages for the draft chapters (the ones without a file) when
+    /// `draft-placeholders` is set, in the order of the chapters.
+    fn draft_placeholders(&self, chapters: &[SelectedChapter]) -> Vec<Option<Chapter>> {
+        // drafts have no path, so they get one from their name
+        let mut ids = HeadingIds::default();
+        chapters
+            .iter()
+            .map(|selected| {
+                let ch = selected.chapter;
+                (self.config.draft_placeholders && ch.path.is_none()).then(|| {
+                    let path = format!("draft-{}.md", ids.unique_id(&ch.name));
+                    debug!(
+                        "Adding placeholder '{}' for draft chapter '{}'",
+                        path, ch.name
+                    );
+                    Chapter {
+                        content: format!(
+                            "# {}\n\n<p class=\"draft\">{}</p>\n",
+                            ch.name, DRAFT_PLACEHOLDER
+                        ),
+                        path: Some(PathBuf::from(path)),
+                        ..ch.clone()
+                    }
+                })
+            })
+            .collect()
+    }
+
+    /// Find the chapter where reading starts: the configured `start-chapter`, or
+    /// else the first numbered chapter, skipping the prefix chapters.
+    fn start_of_content(&self, chapters: &[SelectedChapter]) -> Option<usize> {
+        if let Some(ref start_chapter) = self.config.start_chapter {
+            let start = chapters
+                .iter()
+                .position(|s| s.chapter.path.as_ref() == Some(start_chapter));
+            if start.is_some() {
+                return start;
+            }
+            warn!(
+                "Start chapter '{}' is not found in the book, the start of content is guessed",
+                start_chapter.display()
+            );
+        }
+        chapters
+            .iter()
+            .position(|s| s.number.is_some())
+            .or_else(|| (!chapters.is_empty()).then_some(0))
+    }
+
+    /// Record a landmark for the EPUB 3 navigation document.
+    fn add_landmark(&mut self, landmark: Landmark) {
+        if self.config.epub_version == Some(3) {
+            self.package.landmarks.push(landmark);
+        }
+    }
+
+    /// Mark the first chapter of the front or back matter in the landmarks and the guide.
+    fn add_matter_start(&mut self, chapter_type: ChapterType, path: &str, title: &str) {
+        let (matter, reference_type) = if chapter_type.is_back_matter() {
+            ("backmatter", "other.backmatter")
+        } else {
+            ("frontmatter", "other.frontmatter")
+        };
+        if self.package.has_guide_reference(reference_type) {
+            return;
+        }
+        self.package
+            .guide
+            .push(Landmark::new(reference_type, path, title));
+        self.add_landmark(Landmark::new(matter, path, title));
+    }
+
+    /// Add a page showing the cover image, scaled to fill the screen, as the first
+    /// page of the book.
+    fn add_cover_page(&mut self) -> Result<(), Error> {
+        let Some(cover_image) = self.cover_image.clone() else {
+            return Ok(());
+        };
+        info!("Adding the cover page");
+        let mut ctx = self.book_context();
+        ctx["cover_image"] = json!(cover_image.path);
+        if let Some((width, height)) = cover_image.size {
+            ctx["cover_width"] = json!(width);
+            ctx["cover_height"] = json!(height);
+            if self.config.epub_version == Some(3) {
+                self.package
+                    .manifest_properties
+                    .push((COVER_PAGE_PATH.to_string(), "svg"));
             }
         }
-        info!("3.2 Generate [{}] chapters == ", added_count);
+        let content = self
+            .hbs
+            .render("cover_page", &ctx)
+            .map_err(|e| self.render_error("Cover", "cover_page", e))?;
+        self.builder.add_content(
+            EpubContent::new(COVER_PAGE_PATH, content.as_bytes())
+                .title("Cover")
+                .reftype(ReferenceType::Cover),
+        )?;
+        self.add_landmark(Landmark::new("cover", COVER_PAGE_PATH, "Cover"));
         Ok(())
     }
 
-    fn add_chapter(&mut self, ch: &Chapter, is_first: Option<bool>) -> Result<(), Error> {
-        info!("Adding chapter = '{}'", &ch.name);
-        let rendered_result = self.render_chapter(ch);
-        // let's skip chapter without content (drafts)
-        let rendered = match rendered_result {
+    /// Add the generated title page, along with the logo shown on it.
+    fn add_title_page(&mut self) -> Result<(), Error> {
+        if let Some(logo) = self.config.logo.clone() {
+            let full_path = self.resolve_path(&logo)?;
+            // a logo the chapters show as well is added with the other assets
+            let is_asset = self.assets.values().any(|asset| {
+                asset.filename == logo
+                    || asset.location_on_disk.canonicalize().ok().as_ref() == Some(&full_path)
+            });
+            if is_asset {
+                debug!("The logo {:?} is a chapter asset already", logo);
+            } else {
+                let mt = mime_guess::from_path(&full_path).first_or_octet_stream();
+                let content = file_io(File::open(&full_path), "add-logo", &full_path)?;
+                debug!("Adding logo: {:?} / {:?}", logo, mt.to_string());
+                self.builder.add_resource(&logo, content, mt.to_string())?;
+            }
+        }
+        self.add_generated_page(
+            "title_page",
+            TITLE_PAGE_PATH,
+            "Title Page",
+            ReferenceType::TitlePage,
+            "titlepage",
+        )
+    }
+
+    /// Render one of the generated pages with its template and add it to the book.
+    fn add_generated_page(
+        &mut self,
+        template: &str,
+        path: &str,
+        title: &str,
+        reftype: ReferenceType,
+        landmark: &'static str,
+    ) -> Result<(), Error> {
+        info!("Adding the generated '{}' page", template);
+        let content = self
+            .hbs
+            .render(template, &self.book_context())
+            .map_err(|e| self.render_error(title, template, e))?;
+        self.builder.add_content(
+            EpubContent::new(path, content.as_bytes())
+                .title(title)
+                .reftype(reftype),
+        )?;
+        self.add_landmark(Landmark::new(landmark, path, title));
+        Ok(())
+    }
+
+    /// The book's metadata, as the context for the templates of generated pages.
+    fn book_context(&self) -> serde_json::Value {
+        let mut ctx = self.book_metadata();
+        ctx["epub_version_3"] = json!(self.config.epub_version == Some(3));
+        ctx["stylesheet"] = json!("stylesheet.css");
+        ctx["generator"] = json!(env!("CARGO_PKG_NAME"));
+        ctx["generator_version"] = json!(env!("CARGO_PKG_VERSION"));
+        ctx
+    }
+
+    /// The context for rendering a page of the book with the `index` or a chapter
+    /// template, `chapter` describing the page itself.
+    fn page_context(
+        &self,
+        title: &str,
+        body: String,
+        path_to_root: &str,
+        chapter: serde_json::Value,
+    ) -> serde_json::Value {
+        json!({
+            "epub_version_3": self.config.epub_version == Some(3),
+            "title": title,
+            "body": body,
+            "stylesheet": format!("{path_to_root}stylesheet.css"),
+            "path_to_root": path_to_root,
+            "language": self.language(),
+            "chapter": chapter,
+            "book": self.book_metadata(),
+            "config": self.config,
+        })
+    }
+
+    /// Metadata of the book from `book.toml`.
+    fn book_metadata(&self) -> serde_json::Value {
+        let book = &self.ctx.config.book;
+        json!({
+            "title": book.title.clone().unwrap_or_default(),
+            "subtitle": self.config.subtitle,
+            "authors": book.authors,
+            "description": book.description,
+            "publisher": self.config.publisher,
+            "logo": self.config.logo.as_ref().map(|p| p.to_string_lossy().replace('\\', "/")),
+            "rights": self.config.rights,
+            "edition": self.config.edition,
+            "isbn": self.config.isbn,
+            "language": self.language()
+        })
+    }
+
+    /// Add a table of contents page to the book flow, listing the chapters and,
+    /// down to `inline-toc-depth`, the headings within them.
+    fn add_inline_toc(&mut self, chapters: &[PreparedChapter]) -> Result<(), Error> {
+        info!("Adding the in-book table of contents");
+        let mut entries = Vec::new();
+        for chapter in chapters {
+            let level = chapter.level as usize;
+            entries.push(TocEntry::new(&chapter.path, &chapter.title, level));
+            for heading in &chapter.rendered.headings {
+                let heading_level = heading.level as usize;
+                if (2..=self.config.inline_toc_depth).contains(&heading_level) {
+                    entries.push(TocEntry::new(
+                        format!("{}#{}", chapter.path, heading.id),
+                        &heading.title,
+                        level + heading_level - 1,
+                    ));
+                }
+            }
+        }
+
+        let body = format!(
+            "<h1>{}</h1>\n{}\n",
+            escape_html(TOC_TITLE),
+            render_toc_list(&entries)
+        );
+        let mut ctx = self.page_context(
+            TOC_TITLE,
+            body,
+            "",
+            json!({"name": TOC_TITLE, "path": INLINE_TOC_PATH, "parent_names": [], "depth": 0}),
+        );
+        ctx["epub_type"] = json!("toc");
+        ctx["classes"] = json!("frontmatter toc");
+        let content = self
+            .hbs
+            .render("index", &ctx)
+            .map_err(|e| self.render_error(TOC_TITLE, "index", e))?;
+        self.builder
+            .add_content(EpubContent::new(INLINE_TOC_PATH, content.as_bytes()).title(TOC_TITLE))?;
+        Ok(())
+    }
+
+    /// The language of the book, `en` if not set.
+    fn language(&self) -> String {
+        self.ctx
+            .config
+            .book
+            .language
+            .clone()
+            .unwrap_or_else(|| String::from("en"))
+    }
+
+    /// Render a selected chapter, returning nothing for the chapters that are
+    /// left out of the EPUB.
+    fn prepare_chapter(
+        &mut self,
+        selected: &SelectedChapter,
+        position: &ChapterPosition,
+        is_start: bool,
+    ) -> Result<Option<PreparedChapter>, Error> {
+        let ch = selected.chapter;
+        // draft chapters have no content, and are left out unless `draft-placeholders` is set
+        let Some(content_path) = ch.path.as_ref() else {
+            info!("Leaving out draft chapter '{}'", &ch.name);
+            return Ok(None);
+        };
+        let rendered = match self.render_chapter(ch, selected.matter, position) {
             Ok(rendered_content) => rendered_content,
-            Err(error_msg) => {
-                warn!(
-                    "SKIPPED chapter '{}' due to error = {}",
-                    &ch.name, error_msg
-                );
-                return Ok(());
+            Err(error) if self.config.skip_failed_chapters => {
+                warn!("SKIPPED chapter '{}' due to error = {}", &ch.name, error);
+                return Ok(None);
             }
+            Err(error) => return Err(error),
         };
+        if rendered.front_matter.exclude {
+            info!("Chapter '{}' is excluded by its front matter", &ch.name);
+            return Ok(None);
+        }
 
-        let content_path = ch.path.as_ref().ok_or_else(|| {
-            Error::ContentFileNotFound(format!(
-                "Content file was not found for Chapter '{}'",
-                ch.name
-            ))
-        })?;
         trace!(
             "add a chapter '{:?}' by a path = '{:?}'",
             &ch.name, content_path
@@ -179,100 +680,348 @@ impl<'a> Generator<'a> {
         let path = content_path.with_extension("html").display().to_string();
         let title = if self.config.no_section_label {
             ch.name.clone()
-        } else if let Some(ref section_number) = ch.number {
+        } else if let Some(ref section_number) = selected.number {
             format! {"{} {}", section_number, ch.name}
         } else {
             ch.name.clone()
         };
+        let level = selected
+            .number
+            .as_ref()
+            .map(|n| n.len() as i32 - 1)
+            .unwrap_or(0);
+
+        Ok(Some(PreparedChapter {
+            path,
+            title,
+            level,
+            is_start,
+            rendered,
+        }))
+    }
 
-        // If this is the first chapter, mark its type as Text (i.e. "bodymatter") for render_nav().
-        // This ensures at least one item in the nav.xhtml <nav epub:type="landmarks"><ol> list,
-        // otherwise epubcheck shows an error.
-        let mut content = match is_first {
-            Some(true) => EpubContent::new(path, rendered.as_bytes())
-                .title(title)
-                .reftype(epub_builder::ReferenceType::Text),
-            _ => EpubContent::new(path, rendered.as_bytes()).title(title),
+    fn add_chapter(&mut self, chapter: PreparedChapter) -> Result<(), Error> {
+        let PreparedChapter {
+            path,
+            title,
+            level,
+            is_start,
+            rendered,
+        } = chapter;
+        info!("Adding chapter = '{}'", &title);
+
+        // A chapter type from the front matter takes precedence for the guide. Otherwise, if
+        // this is where reading starts, mark its type as Text (i.e. "bodymatter").
+        let chapter_type = rendered.front_matter.chapter_type;
+        let reftype = match chapter_type.and_then(|t| t.reference_type()) {
+            Some(reftype) => Some(reftype),
+            None => is_start.then_some(ReferenceType::Text),
         };
-
-        let level = ch.number.as_ref().map(|n| n.len() as i32 - 1).unwrap_or(0);
+        if is_start {
+            self.add_landmark(Landmark::new("bodymatter", &path, &title));
+        }
+        if let Some(chapter_type) = chapter_type {
+            self.add_matter_start(chapter_type, &path, &title);
+            if let Some(epub_type) = chapter_type.landmark() {
+                self.add_landmark(Landmark::new(epub_type, &path, &title));
+            }
+        }
+        if rendered.mathml {
+            self.package
+                .manifest_properties
+                .push((path.clone(), "mathml"));
+        }
+        let headings = heading_toc_elements(&path, &rendered.headings, self.config.toc_depth);
+        let mut content = EpubContent::new(path, rendered.content.as_bytes()).title(title);
+        if let Some(reftype) = reftype {
+            content = content.reftype(reftype);
+        }
         content = content.level(level);
+        for heading in headings {
+            content = content.child(heading);
+        }
 
         self.builder.add_content(content)?;
 
         Ok(())
     }
 
-    /// Render the chapter into its fully formed HTML representation.
-    fn render_chapter(&mut self, ch: &Chapter) -> Result<String, RenderError> {
-        let chapter_dir = if let Some(chapter_file_path) = &ch.path {
-            chapter_file_path.parent().ok_or_else(|| {
-                RenderError::from(RenderErrorReason::Other(format!(
-                    "No CSS found by a path = {:?}",
-                    ch.path
-                )))
-            })?
-        } else {
-            return Err(RenderError::from(RenderErrorReason::Other(format!(
-                "Draft chapter: '{}' could not be rendered.",
+    /// Render the chapter into its fully formed HTML representation. The chapter's
+    /// front matter is stripped from the content before rendering. Unless the front
+    /// matter says otherwise, the chapter gets the type given by `matter`, i.e. the
+    /// front or back matter type of prefix and suffix chapters.
+    fn render_chapter(
+        &mut self,
+        ch: &Chapter,
+        matter: Option<ChapterType>,
+        position: &ChapterPosition,
+    ) -> Result<RenderedChapter, Error> {
+        let Some(chapter_file_path) = &ch.path else {
+            return Err(Error::ContentFileNotFound(format!(
+                "Draft chapter '{}' could not be rendered",
                 ch.name
-            ))));
+            )));
         };
+        let chapter_dir = chapter_file_path.parent().ok_or_else(|| {
+            Error::ContentFileNotFound(format!("No CSS found by a path = {:?}", ch.path))
+        })?;
+
+        let (mut front_matter, chapter_content) =
+            FrontMatter::split(&ch.content).map_err(|e| Error::FrontMatter {
+                chapter: ch.name.clone(),
+                source: Box::new(e),
+            })?;
+        front_matter.chapter_type = front_matter.chapter_type.or(matter);
+        if front_matter.exclude {
+            return Ok(RenderedChapter {
+                content: String::new(),
+                front_matter,
+                headings: Vec::new(),
+                ids: HeadingIds::default(),
+                anchor_links: Vec::new(),
+                notes: String::new(),
+                mathml: false,
+            });
+        }
 
         let mut body = String::with_capacity(3000); // big enough arbitrary size
 
-        let parser = utils::create_new_pull_down_parser(&ch.content);
-        let mut quote_converter = QuoteConverterFilter::new(self.config.curly_quotes);
+        let epub_version_3 = self.config.epub_version == Some(3);
+        let chapter_content = admonition::expand_admonish_blocks(chapter_content, epub_version_3);
+        let math = self.config.math.unwrap_or(false);
+        let path_to_root: String = chapter_dir.components().map(|_| "../").collect();
+        let diagram_cache = self.diagram_cache();
+        let mut diagram_filter = DiagramFilter::new(
+            &self.config.code_renderers,
+            &self.ctx.root,
+            &diagram_cache,
+            &path_to_root,
+        );
+        let mut playground_filter = PlaygroundFilter::new(self.ctx.config.rust.edition);
+        let mut code_block_filter =
+            CodeBlockFilter::new(self.config.code_line_numbers, self.config.code_wrap_column);
+        let parser = TextMergeStream::new(utils::create_new_pull_down_parser(
+            &chapter_content,
+            self.config.markdown_options(),
+        ));
+        let language = front_matter
+            .language
+            .clone()
+            .unwrap_or_else(|| self.language());
+        let quote_style = self
+            .config
+            .quote_style
+            .unwrap_or_else(|| QuoteStyle::for_language(&language));
+        let mut math_filter = MathFilter::new(epub_version_3);
+        let admonition_filter = AdmonitionFilter::new(epub_version_3);
+        let mut typography = TypographyFilter::new(self.config.curly_quotes)
+            .with_style(quote_style)
+            .with_dashes(self.config.smart_dashes)
+            .with_ellipses(self.config.smart_ellipses)
+            .with_non_breaking_spaces(self.config.non_breaking_spaces, &language);
+        if self.config.hyphenate && !self.hyphenators.contains_key(&language) {
+            let dictionary = hyphenate::dictionary(&language)?;
+            if dictionary.is_none() {
+                warn!("There is no hyphenation dictionary for the language '{language}'");
+            }
+            self.hyphenators.insert(language.clone(), dictionary);
+        }
+        let mut hyphenation_filter = HyphenationFilter::new(
+            self.hyphenators.get(&language).and_then(Option::as_ref),
+            self.config.hyphenate_min_length,
+        );
+        let mut heading_filter = HeadingFilter::new();
+        let mut chapter_link_filter = ChapterLinkFilter::new(chapter_file_path);
         let ch_depth = chapter_dir.components().count();
 
         debug!("There are = {:?}", self.assets);
         let mut asset_link_filter =
             AssetRemoteLinkFilter::new(&mut self.assets, ch_depth, &*self.handler);
 
-        let mut footnote_filter =
-            if self.config.epub_version == Some(3) && self.config.footnote_backrefs {
-                FootnoteFilter::new(self.config.footnote_backrefs)
-            } else {
-                FootnoteFilter::new(false)
-            };
+        let note_markup = if !epub_version_3 {
+            NoteMarkup::Plain
+        } else if self.config.ibooks_footnotes {
+            NoteMarkup::Div
+        } else {
+            NoteMarkup::Aside
+        };
+        let footnote_filter = if self.config.endnotes {
+            FootnoteFilter::endnotes(NoteLinks {
+                notes_href: format!("{path_to_root}{NOTES_PATH}"),
+                chapter_href: xhtml_path(chapter_file_path),
+                id_prefix: format!("{}-", id_from_content(&chapter_path_id(chapter_file_path))),
+                first_number: match self.config.endnote_numbering {
+                    EndnoteNumbering::Chapter => 1,
+                    EndnoteNumbering::Book => self.note_count + 1,
+                },
+            })
+        } else {
+            FootnoteFilter::new(self.config.footnote_backrefs)
+        };
+        let mut footnote_filter = footnote_filter.with_markup(note_markup);
 
         let events = parser
-            .map(|event| quote_converter.apply(event))
+            .filter_map(|event| diagram_filter.apply(event))
+            .flat_map(|event| {
+                if self.config.playground_links {
+                    playground_filter.apply(event)
+                } else {
+                    vec![event]
+                }
+            })
+            .filter_map(|event| code_block_filter.apply(event))
+            .flat_map(|event| {
+                if math {
+                    math_filter.apply(event)
+                } else {
+                    vec![event]
+                }
+            })
+            .map(|event| admonition_filter.apply(event))
+            .map(|event| typography.apply(event))
+            .map(|event| hyphenation_filter.apply(event))
+            .flat_map(|event| heading_filter.apply(event))
+            .map(|event| chapter_link_filter.apply(event))
             .map(|event| asset_link_filter.apply(event))
             .filter_map(|event| footnote_filter.apply(event));
 
         trace!("Found Rendering events map = [{:?}]", &events);
 
         html::push_html(&mut body, events);
+        let (headings, ids) = heading_filter.into_parts();
 
+        let mut notes = String::new();
         if !footnote_filter.is_empty() {
             footnote_filter.retain();
             footnote_filter.sort_by_cached_key();
-            body.push_str("<div class=\"footnotes\" epub:type=\"footnotes\">\n");
             let events = footnote_filter.get_events();
-            html::push_html(&mut body, events);
-            body.push_str("</div>\n");
+            if self.config.endnotes {
+                // moved to the notes chapter at the end of the book
+                html::push_html(&mut notes, events);
+            } else {
+                body.push_str(if epub_version_3 {
+                    "<div class=\"footnotes\" epub:type=\"footnotes\">\n"
+                } else {
+                    "<div class=\"footnotes\">\n"
+                });
+                html::push_html(&mut body, events);
+                body.push_str("</div>\n");
+            }
         }
+        self.note_count += footnote_filter.note_count();
+        let diagrams = diagram_filter.into_images()?;
+        self.add_diagrams(&diagram_cache, diagrams)?;
 
         trace!("Chapter content after Events processing = [{:?}]", body);
 
-        let stylesheet_path = chapter_dir
-            .components()
-            .map(|_| "..")
-            .chain(iter::once("stylesheet.css"))
-            .collect::<Vec<_>>()
-            .join("/");
+        let epub_type = front_matter.chapter_type.map(|t| t.epub_type());
+        // classes to style front, body and back matter chapters differently
+        let classes = match front_matter.chapter_type {
+            None => String::from("bodymatter"),
+            Some(ChapterType::Frontmatter) => String::from("frontmatter"),
+            Some(ChapterType::Backmatter) => String::from("backmatter"),
+            Some(chapter_type) => format!("backmatter {}", chapter_type.epub_type()),
+        };
 
-        let epub_version_3 = self.config.epub_version == Some(3);
+        let chapter_link = |ch: &Chapter| {
+            ch.path.as_ref().map(|path| {
+                let path = xhtml_path(path);
+                json!({
+                    "name": ch.name,
+                    "path": path,
+                    "link": format!("{path_to_root}{path}")
+                })
+            })
+        };
 
-        let ctx = json!({
-            "epub_version_3": epub_version_3,
-            "title": ch.name,
-            "body": body,
-            "stylesheet": stylesheet_path
+        let mut ctx = self.page_context(
+            &ch.name,
+            body,
+            &path_to_root,
+            json!({
+                "name": ch.name,
+                "number": position.number.map(|n| n.to_string()),
+                "path": xhtml_path(chapter_file_path),
+                "source_path": chapter_file_path.to_string_lossy().replace('\\', "/"),
+                "parent_names": ch.parent_names,
+                "depth": ch.parent_names.len(),
+            }),
+        );
+        ctx["language"] = json!(language);
+        ctx["epub_type"] = json!(epub_type);
+        ctx["classes"] = json!(classes);
+        ctx["previous"] = json!(position.previous.and_then(chapter_link));
+        ctx["next"] = json!(position.next.and_then(chapter_link));
+
+        // the front matter's template takes precedence over the `chapter-templates`
+        let template_file = front_matter.template.clone().or_else(|| {
+            self.chapter_templates
+                .find(chapter_file_path)
+                .map(Path::to_path_buf)
         });
+        let template = match template_file {
+            Some(ref template_file) => self.register_chapter_template(template_file)?,
+            None => String::from("index"),
+        };
+        let content = self
+            .hbs
+            .render(&template, &ctx)
+            .map_err(|e| self.render_error(&ch.name, &template, e))?;
+
+        Ok(RenderedChapter {
+            content,
+            front_matter,
+            headings,
+            ids,
+            anchor_links: chapter_link_filter.into_anchor_links(),
+            notes,
+            mathml: math_filter.has_mathml(),
+        })
+    }
 
-        self.hbs.render("index", &ctx)
+    /// Register a template requested by a chapter's front matter (relative to the
+    /// book root), returning the name it was registered under.
+    fn register_chapter_template(&mut self, template_file: &Path) -> Result<String, Error> {
+        let name = template_file.display().to_string();
+        if !self.hbs.has_template(&name) {
+            let full_path = self.ctx.root.join(template_file);
+            let template = std::fs::read_to_string(&full_path)
+                .map_err(|_| Error::OpenTemplate(full_path.clone()))?;
+            self.hbs
+                .register_template_string(&name, template)
+                .map_err(|e| Error::template_parse(&name, e))?;
+        }
+        Ok(name)
+    }
+
+    /// The error for rendering a page with the template registered as `template`,
+    /// naming the template or partial the error is in.
+    fn render_error(&self, page: &str, template: &str, e: handlebars::RenderError) -> Error {
+        // the error may be in a partial used by the template
+        let failed = e
+            .template_name
+            .clone()
+            .unwrap_or_else(|| template.to_string());
+        let template_file = match failed.as_str() {
+            "index" => &self.config.index_template,
+            "title_page" => &self.config.title_page_template,
+            "copyright_page" => &self.config.copyright_page_template,
+            "cover" => &self.config.cover_template,
+            "cover_page" => &None,
+            // chapter templates are registered by their path, partials by their
+            // path in the theme dir
+            _ => {
+                let partial = self
+                    .config
+                    .theme_dir
+                    .as_ref()
+                    .map(|dir| dir.join(format!("{failed}.hbs")))
+                    .filter(|path| path.is_file());
+                let label = partial.map_or(failed, |path| path.display().to_string());
+                return Error::chapter_render(page, label, e);
+            }
+        };
+        Error::chapter_render(page, template_label(&failed, template_file), e)
     }
 
     /// Generate the stylesheet and add it to the document.
@@ -309,6 +1058,32 @@ impl<'a> Generator<'a> {
         Ok(())
     }
 
+    /// Where the images rendered from code blocks are cached between builds.
+    fn diagram_cache(&self) -> PathBuf {
+        self.ctx.destination.join("diagram-cache")
+    }
+
+    /// Add the images rendered from a chapter's code blocks, unless an earlier
+    /// chapter already rendered the same code.
+    fn add_diagrams(&mut self, cache_dir: &Path, file_names: Vec<String>) -> Result<(), Error> {
+        for file_name in file_names {
+            if self.diagrams.contains(&file_name) {
+                continue;
+            }
+            let path = CodeRenderer::cached_image(cache_dir, &file_name);
+            let mt = mime_guess::from_path(&path).first_or_octet_stream();
+            let content = file_io(File::open(&path), "add-diagram", &path)?;
+            debug!("Adding rendered code block: {:?}", path);
+            self.builder.add_resource(
+                format!("{DIAGRAMS_DIR}/{file_name}"),
+                content,
+                mt.to_string(),
+            )?;
+            self.diagrams.insert(file_name);
+        }
+        Ok(())
+    }
+
     fn additional_resources(&mut self) -> Result<(), Error> {
         info!("7. Embedding additional resources ==");
 
@@ -334,21 +1109,62 @@ impl<'a> Generator<'a> {
     }
 
     fn add_cover_image(&mut self) -> Result<(), Error> {
-        info!("4. Adding cover image ==");
+        info!("3. Adding cover image ==");
 
         if let Some(ref path) = self.config.cover_image {
             let full_path = self.resolve_path(path)?;
             let mt = mime_guess::from_path(&full_path).first_or_octet_stream();
 
-            let content = file_io(File::open(&full_path), "add-cover-image", &full_path)?;
+            let content = file_io(std::fs::read(&full_path), "add-cover-image", &full_path)?;
             debug!("Adding cover image: {:?} / {:?} ", path, mt.to_string());
             self.builder
-                .add_cover_image(path, content, mt.to_string())?;
+                .add_cover_image(path, content.as_slice(), mt.to_string())?;
+            self.cover_image = Some(CoverImage::new(
+                &path.to_string_lossy(),
+                image_size(&content),
+            ));
+        } else if self.config.generate_cover {
+            let cover = self.generate_cover()?;
+            debug!("Adding generated cover image: {:?}", GENERATED_COVER_PATH);
+            self.builder.add_cover_image(
+                GENERATED_COVER_PATH,
+                cover.as_bytes(),
+                "image/svg+xml",
+            )?;
+            self.cover_image = Some(CoverImage::new(
+                GENERATED_COVER_PATH,
+                Some(GENERATED_COVER_SIZE),
+            ));
         }
 
         Ok(())
     }
 
+    /// Render the SVG cover from the book's title, subtitle and authors.
+    fn generate_cover(&self) -> Result<String, Error> {
+        let mut ctx = self.book_context();
+        let title = self.ctx.config.book.title.clone().unwrap_or_default();
+        ctx["title_lines"] = json!(wrap_text(&title, TITLE_LINE_LENGTH));
+        let subtitle = self.config.subtitle.as_deref().unwrap_or_default();
+        ctx["subtitle_lines"] = json!(wrap_text(subtitle, SUBTITLE_LINE_LENGTH));
+        ctx["background"] = json!(self.config.cover_background);
+        ctx["text_color"] = json!(self.config.cover_text_color);
+        if let Some(ref path) = self.config.cover_background_image {
+            // embedded, as images referenced by an SVG image aren't loaded by readers
+            let full_path = self.resolve_path(path)?;
+            let mt = mime_guess::from_path(&full_path).first_or_octet_stream();
+            let content = file_io(std::fs::read(&full_path), "read", &full_path)?;
+            ctx["background_image"] = json!(format!(
+                "data:{};base64,{}",
+                mt,
+                BASE64_STANDARD.encode(content)
+            ));
+        }
+        self.hbs
+            .render("cover", &ctx)
+            .map_err(|e| self.render_error("Cover", "cover", e))
+    }
+
     /// Concatenate all provided stylesheets into one long stylesheet.
     fn generate_stylesheet(&self) -> Result<Vec<u8>, Error> {
         let mut stylesheet = Vec::new();
@@ -403,12 +1219,67 @@ impl Debug for Generator<'_> {
     }
 }
 
+/// Warn about links to anchors that the chapters they point at don't have.
+fn check_anchor_links(chapters: &[PreparedChapter]) {
+    let ids: HashMap<PathBuf, &HeadingIds> = chapters
+        .iter()
+        .map(|c| (PathBuf::from(&c.path), &c.rendered.ids))
+        .collect();
+    for chapter in chapters {
+        for link in &chapter.rendered.anchor_links {
+            if let Some(target_ids) = ids.get(&link.chapter)
+                && !target_ids.contains(&link.anchor)
+            {
+                warn!(
+                    "Chapter '{}' links to '{}#{}', which has no heading with this id",
+                    chapter.title,
+                    link.chapter.display(),
+                    link.anchor
+                );
+            }
+        }
+    }
+}
+
+/// How a registered template is named in error messages: the user's template file
+/// when there is one, otherwise the built-in template.
+fn template_label(name: &str, template_file: &Option<PathBuf>) -> String {
+    match template_file {
+        Some(path) => path.display().to_string(),
+        None => format!("built-in {name} template"),
+    }
+}
+
+/// Whether the chapter ends up in the EPUB: drafts and chapters excluded by their
+/// front matter don't.
+fn is_readable(ch: &Chapter) -> bool {
+    ch.path.is_some() && FrontMatter::split(&ch.content).is_ok_and(|(fm, _)| !fm.exclude)
+}
+
+/// The chapter path without extension and with dashes for the directory
+/// separators, e.g. `guide-setup` for `guide/setup.md`.
+fn chapter_path_id(chapter_path: &Path) -> String {
+    chapter_path
+        .with_extension("")
+        .to_string_lossy()
+        .replace(['/', '\\'], "-")
+}
+
+/// Path of the chapter's XHTML file in the EPUB, with forward slashes.
+fn xhtml_path(chapter_path: &Path) -> String {
+    chapter_path
+        .with_extension("html")
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::init_tracing;
     use crate::resources::asset::AssetKind;
     use crate::resources::retrieve::{MockContentRetriever, RetrievedContent, UpdatedAssetData};
+    use mdbook_core::book::BookItem;
     use mime_guess::mime;
     use std::io::Cursor;
     use std::path::Path;
@@ -419,7 +1290,7 @@ mod tests {
     static INIT: Once = Once::new();
     pub fn init_logging() {
         INIT.call_once(|| {
-            let _ = init_tracing();
+            init_tracing();
         });
     }
 
@@ -450,13 +1321,12 @@ mod tests {
         let book_source = PathBuf::from(&ctx.root)
             .join(&ctx.config.book.src)
             .canonicalize()
-            .expect(
-                format!(
+            .unwrap_or_else(|_| {
+                panic!(
                     "book source root is not found: {}",
                     &ctx.config.book.src.display()
                 )
-                .as_str(),
-            );
+            });
         let should_be_png = book_source.join(png);
         let should_be_svg = book_source.join(svg);
         let hashed_filename = utils::hash_link(&url.parse::<Url>().unwrap());
@@ -543,7 +1413,8 @@ mod tests {
         let test_content_retriever = TestHandler {};
 
         let mut filter = AssetRemoteLinkFilter::new(&mut assets, 0, &test_content_retriever);
-        let parser = utils::create_new_pull_down_parser(&markdown_str);
+        let parser =
+            utils::create_new_pull_down_parser(&markdown_str, Config::default().markdown_options());
         let events = parser.map(|ev| filter.apply(ev));
         trace!("Events = {:?}", events);
         let mut html_buf = String::new();
@@ -612,18 +1483,24 @@ mod tests {
         g.find_assets().unwrap();
         assert_eq!(g.assets.len(), 1);
 
-        let pat = |heading, prefix| {
-            format!("<h1>{heading}</h1>\n<p><img src=\"{prefix}be8d297f5c063a3c.svg\"")
+        let pat = |heading, id, prefix| {
+            format!("<h1 id=\"{id}\">{heading}</h1>\n<p><img src=\"{prefix}be8d297f5c063a3c.svg\"")
         };
         if let BookItem::Chapter(ref ch) = ctx.book.items[0] {
-            let rendered: String = g.render_chapter(ch).unwrap();
+            let rendered = g
+                .render_chapter(ch, None, &ChapterPosition::default())
+                .unwrap()
+                .content;
             debug!("1. rendered ===\n{}", &rendered);
-            assert!(rendered.contains(&pat("Chapter 1", "../")));
+            assert!(rendered.contains(&pat("Chapter 1", "chapter-1", "../")));
 
             if let BookItem::Chapter(ref sub_ch) = ch.sub_items[0] {
-                let sub_rendered = g.render_chapter(sub_ch).unwrap();
+                let sub_rendered = g
+                    .render_chapter(sub_ch, None, &ChapterPosition::default())
+                    .unwrap()
+                    .content;
                 debug!("2. rendered ===\n{}", &sub_rendered);
-                assert!(sub_rendered.contains(&pat("Subchapter", "../")));
+                assert!(sub_rendered.contains(&pat("Subchapter", "subchapter", "../")));
             } else {
                 panic!();
             }
@@ -631,13 +1508,173 @@ mod tests {
             panic!();
         }
         if let BookItem::Chapter(ref ch) = ctx.book.items[1] {
-            let rendered: String = g.render_chapter(ch).unwrap();
-            assert!(rendered.contains(&pat("Chapter 2", "")));
+            let rendered = g
+                .render_chapter(ch, None, &ChapterPosition::default())
+                .unwrap()
+                .content;
+            assert!(rendered.contains(&pat("Chapter 2", "chapter-2", "")));
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn test_render_chapter_with_front_matter() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let content = "+++\ntype = \"appendix\"\nlanguage = \"de\"\n+++\n# Anhang\n";
+        let mut json =
+            ctx_with_template(content, "src", tmp_dir.path().join("mdbook-epub").as_path());
+        json["config"]["output"]["epub"]["epub-version"] = json!(3);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+
+        if let BookItem::Chapter(ref ch) = ctx.book.items[0] {
+            let rendered = g
+                .render_chapter(ch, None, &ChapterPosition::default())
+                .unwrap();
+            assert_eq!(rendered.front_matter.language.as_deref(), Some("de"));
+            assert!(!rendered.content.contains("+++"));
+            assert!(rendered.content.contains("xml:lang=\"de\" lang=\"de\""));
+            assert!(
+                rendered
+                    .content
+                    .contains("<body class=\"backmatter appendix\" epub:type=\"appendix\">")
+            );
+            assert!(rendered.content.contains("<h1 id=\"anhang\">Anhang</h1>"));
+        } else {
+            panic!();
+        }
+    }
+
+    #[test]
+    fn test_render_chapter_excluded_by_front_matter() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let content = "+++\nexclude = true\n+++\n# Playground\n";
+        let json = ctx_with_template(content, "src", tmp_dir.path().join("mdbook-epub").as_path());
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+        g.find_assets().unwrap();
+
+        if let BookItem::Chapter(ref ch) = ctx.book.items[0] {
+            let rendered = g
+                .render_chapter(ch, None, &ChapterPosition::default())
+                .unwrap();
+            assert!(rendered.front_matter.exclude);
+            assert!(rendered.content.is_empty());
         } else {
             panic!();
         }
     }
 
+    #[test]
+    fn test_template_parse_error_has_location() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let template = tmp_dir.path().join("broken.hbs");
+        std::fs::write(&template, "<body>\n{{#if title}}\n{{ body }\n</body>\n").unwrap();
+        let mut json = ctx_with_template(
+            "# Chapter",
+            "src",
+            tmp_dir.path().join("mdbook-epub").as_path(),
+        );
+        json["config"]["output"]["epub"]["index-template"] = json!(template);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+
+        match Generator::new(&ctx) {
+            Err(Error::TemplateParse {
+                template: name,
+                line,
+                ..
+            }) => {
+                assert_eq!(name, template.display().to_string());
+                assert_eq!(line, Some(3));
+            }
+            other => panic!("expected a template parse error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_chapter_render_error_is_fatal() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let template = tmp_dir.path().join("chapter.hbs");
+        std::fs::write(&template, "<body>\n{{> missing}}\n</body>\n").unwrap();
+        let mut json = ctx_with_template(
+            "# Chapter",
+            "src",
+            tmp_dir.path().join("mdbook-epub").as_path(),
+        );
+        json["config"]["output"]["epub"]["index-template"] = json!(template);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+        g.find_assets().unwrap();
+
+        let BookItem::Chapter(ref ch) = ctx.book.items[0] else {
+            panic!();
+        };
+        let selected = SelectedChapter {
+            chapter: ch,
+            number: None,
+            matter: None,
+        };
+        let error = g
+            .prepare_chapter(&selected, &ChapterPosition::default(), false)
+            .err()
+            .unwrap();
+        assert!(matches!(error, Error::ChapterRender { line: Some(2), .. }));
+        let message = error.to_string();
+        assert!(message.starts_with(&format!(
+            "Could not render chapter 'Chapter 1' with the template '{}' at line 2, column 1: ",
+            template.display()
+        )));
+        assert!(message.contains("missing"));
+
+        g.config.skip_failed_chapters = true;
+        let prepared = g
+            .prepare_chapter(&selected, &ChapterPosition::default(), false)
+            .unwrap();
+        assert!(prepared.is_none());
+    }
+
+    #[test]
+    fn test_chapter_failures_are_collected() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let template = tmp_dir.path().join("chapter.hbs");
+        std::fs::write(&template, "<body>{{> missing}}</body>").unwrap();
+        let mut json = ctx_with_template(
+            "# Chapter",
+            "src",
+            tmp_dir.path().join("mdbook-epub").as_path(),
+        );
+        json["config"]["output"]["epub"]["index-template"] = json!(template);
+        let mut second = json["book"]["items"][0].clone();
+        second["Chapter"]["name"] = json!("Chapter 2");
+        second["Chapter"]["path"] = json!("chapter_2.md");
+        json["book"]["items"].as_array_mut().unwrap().extend([
+            json!({"Chapter": {"name": "Draft", "content": "", "number": [3],
+                "sub_items": [], "path": null, "parent_names": []}}),
+            second,
+        ]);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+        g.find_assets().unwrap();
+
+        let error = g.generate_chapters().err().unwrap();
+        let Error::ChapterFailures(ref failures) = error else {
+            panic!("expected chapter failures, got {error:?}");
+        };
+        // the draft is left out, not failed
+        assert_eq!(failures.len(), 2);
+        let message = error.to_string();
+        assert!(message.starts_with(
+            "2 chapter(s) failed to render:\n  - Could not render chapter 'Chapter 1' with the template "
+        ));
+        assert!(message.contains("\n  - Could not render chapter 'Chapter 2' with the template "));
+    }
+
     #[test]
     #[should_panic]
     fn test_find_assets_with_wrong_src_dir() {
@@ -654,6 +1691,60 @@ mod tests {
         g.find_assets().unwrap();
     }
 
+    #[test]
+    fn test_generated_page_render_error_has_location() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let template = tmp_dir.path().join("title_page.hbs");
+        std::fs::write(&template, "<body>\n{{> missing}}\n</body>\n").unwrap();
+        let mut json = ctx_with_template(
+            "# Chapter",
+            "src",
+            tmp_dir.path().join("mdbook-epub").as_path(),
+        );
+        json["config"]["output"]["epub"]["title-page"] = json!(true);
+        json["config"]["output"]["epub"]["title-page-template"] = json!(template);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+
+        let error = g.add_title_page().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Could not render chapter 'Title Page' with the template '{}' at line 2, column 1: Partial not found missing",
+                template.display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_partial_render_error_names_the_partial() {
+        init_logging();
+        let tmp_dir = TempDir::new().unwrap();
+        let theme_dir = tmp_dir.path().join("theme");
+        std::fs::create_dir(&theme_dir).unwrap();
+        std::fs::write(theme_dir.join("toc_header.hbs"), "<p>\n{{> missing}}</p>\n").unwrap();
+        let template = tmp_dir.path().join("chapter.hbs");
+        std::fs::write(&template, "<body>{{> toc_header}}{{{ body }}}</body>\n").unwrap();
+        let mut json = ctx_with_template(
+            "# Chapter",
+            "src",
+            tmp_dir.path().join("mdbook-epub").as_path(),
+        );
+        json["config"]["output"]["epub"]["inline-toc"] = json!(true);
+        json["config"]["output"]["epub"]["index-template"] = json!(template);
+        json["config"]["output"]["epub"]["theme-dir"] = json!(theme_dir);
+        let ctx = RenderContext::from_json(json.to_string().as_bytes()).unwrap();
+        let mut g = Generator::new(&ctx).unwrap();
+
+        let error = g.add_inline_toc(&[]).unwrap_err();
+        let message = error.to_string();
+        assert!(message.starts_with(&format!(
+            "Could not render chapter 'Table Of Contents' with the template '{}' at line 2",
+            theme_dir.join("toc_header.hbs").display()
+        )));
+    }
+
     fn ctx_with_template(content: &str, source: &str, destination: &Path) -> serde_json::Value {
         json!({
             "version": mdbook_core::MDBOOK_VERSION,
@@ -13,18 +13,27 @@ use tracing::{debug, info};
 
 use errors::Error;
 
-pub use crate::config::Config;
+pub use crate::config::{Config, EndnoteNumbering, MarkdownExtensions, QuoteStyle};
+pub use crate::diagram::{CodeRenderer, ImageFormat};
 pub use crate::generator::Generator;
+pub use crate::theme::ChapterTemplate;
 use crate::validation::validate_config_title_file_name;
 
 mod config;
+mod cover;
+mod diagram;
 pub mod errors;
 mod filters;
+mod front_matter;
 mod generator;
+pub mod init_trace;
+mod package;
 mod resources;
+mod selection;
+mod theme;
+mod toc;
 mod utils;
 mod validation;
-pub mod init_trace;
 // Reexport function
 pub use init_trace::init_tracing;
 
@@ -102,10 +111,7 @@ pub fn file_io<T>(
     })
 }
 
-pub fn path_io<T>(
-    result: std::io::Result<T>,
-    path: impl Into<PathBuf>,
-) -> Result<T, Error> {
+pub fn path_io<T>(result: std::io::Result<T>, path: impl Into<PathBuf>) -> Result<T, Error> {
     result.map_err(|e| Error::AssetPathIo {
         path: path.into(),
         source: e,
@@ -0,0 +1,229 @@
+use crate::Error;
+use mdbook_core::utils::{escape_html, escape_html_attribute};
+use std::fmt::Write as _;
+use std::io::{Cursor, Read, Write};
+use tracing::debug;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+const NAV_PATH: &str = "OEBPS/nav.xhtml";
+const OPF_PATH: &str = "OEBPS/content.opf";
+
+/// A navigation landmark, listed in the `landmarks` nav of an EPUB 3 `nav.xhtml`.
+/// Guide references of the package document have the same shape.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Landmark {
+    /// Structural semantics value, e.g. `bodymatter` or `backmatter`.
+    pub epub_type: &'static str,
+    pub href: String,
+    pub title: String,
+}
+
+impl Landmark {
+    pub(crate) fn new(
+        epub_type: &'static str,
+        href: impl Into<String>,
+        title: impl Into<String>,
+    ) -> Self {
+        Landmark {
+            epub_type,
+            href: href.into().replace('\\', "/"),
+            title: title.into(),
+        }
+    }
+}
+
+/// Changes to the package documents generated by `epub-builder`, for things its
+/// API can't express. They are collected while the book is generated and applied
+/// to the finished archive.
+#[derive(Debug, Default)]
+pub(crate) struct PackageAdjustments {
+    /// Landmarks replacing the ones `epub-builder` derives from reference types,
+    /// which only cover a part of the EPUB 3 landmark types.
+    pub landmarks: Vec<Landmark>,
+    /// Guide references added to the ones `epub-builder` derives from reference
+    /// types, e.g. `other.backmatter`.
+    pub guide: Vec<Landmark>,
+    /// Manifest properties of content documents, e.g. `svg` for documents with
+    /// inline SVG, which `epub-builder` doesn't set (EPUB 3 only).
+    pub manifest_properties: Vec<(String, &'static str)>,
+}
+
+impl PackageAdjustments {
+    pub(crate) fn has_guide_reference(&self, reference_type: &str) -> bool {
+        self.guide.iter().any(|r| r.epub_type == reference_type)
+    }
+
+    /// Rewrite the EPUB archive, replacing the adjusted package documents and
+    /// copying every other entry as is.
+    pub(crate) fn apply(&self, epub: Vec<u8>) -> Result<Vec<u8>, Error> {
+        if self.landmarks.is_empty() && self.guide.is_empty() && self.manifest_properties.is_empty()
+        {
+            return Ok(epub);
+        }
+
+        let mut archive = ZipArchive::new(Cursor::new(epub))?;
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for idx in 0..archive.len() {
+            let mut file = archive.by_index(idx)?;
+            if file.name() == NAV_PATH && !self.landmarks.is_empty() {
+                debug!("Applying landmarks to '{}'", NAV_PATH);
+                let mut nav = String::new();
+                file.read_to_string(&mut nav)?;
+                writer.start_file(NAV_PATH, SimpleFileOptions::default())?;
+                writer.write_all(self.apply_to_nav(&nav).as_bytes())?;
+            } else if file.name() == OPF_PATH
+                && !(self.guide.is_empty() && self.manifest_properties.is_empty())
+            {
+                debug!("Applying guide references and properties to '{}'", OPF_PATH);
+                let mut opf = String::new();
+                file.read_to_string(&mut opf)?;
+                writer.start_file(OPF_PATH, SimpleFileOptions::default())?;
+                writer.write_all(self.apply_to_opf(&opf).as_bytes())?;
+            } else {
+                writer.raw_copy_file(file)?;
+            }
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    /// Replace the contents of the landmarks nav, if the document has one (EPUB 3 only).
+    fn apply_to_nav(&self, nav: &str) -> String {
+        let Some(start) = nav
+            .find("\"landmarks\"")
+            .and_then(|pos| nav[pos..].find('>').map(|end| pos + end + 1))
+        else {
+            return nav.to_string();
+        };
+        let Some(end) = nav[start..].find("</nav>").map(|end| start + end) else {
+            return nav.to_string();
+        };
+
+        let mut list = String::from("\n    <ol>\n");
+        for landmark in &self.landmarks {
+            writeln!(
+                list,
+                "      <li><a epub:type=\"{}\" href=\"{}\">{}</a></li>",
+                landmark.epub_type,
+                escape_html_attribute(&landmark.href),
+                escape_html(&landmark.title)
+            )
+            .unwrap();
+        }
+        list.push_str("    </ol>\n  ");
+
+        format!("{}{}{}", &nav[..start], list, &nav[end..])
+    }
+
+    /// Set the manifest properties and append the extra references to the guide
+    /// of the package document.
+    fn apply_to_opf(&self, opf: &str) -> String {
+        let mut opf = opf.to_string();
+        for (href, property) in &self.manifest_properties {
+            let href_attr = format!(
+                "href=\"{}\"",
+                escape_html_attribute(&href.replace('\\', "/"))
+            );
+            let Some(item_start) = opf
+                .find(&href_attr)
+                .and_then(|pos| opf[..pos].rfind("<item "))
+            else {
+                continue;
+            };
+            opf.insert_str(
+                item_start + "<item ".len(),
+                &format!("properties=\"{property}\" "),
+            );
+        }
+
+        let Some(end) = opf.find("</guide>") else {
+            return opf;
+        };
+
+        let mut references = String::new();
+        for reference in &self.guide {
+            write!(
+                references,
+                "  <reference type=\"{}\" title=\"{}\" href=\"{}\"/>\n  ",
+                reference.epub_type,
+                escape_html_attribute(&reference.title),
+                escape_html_attribute(&reference.href)
+            )
+            .unwrap();
+        }
+
+        format!("{}{}{}", &opf[..end], references, &opf[end..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAV: &str = r#"<body>
+  <nav epub:type = "toc" id="toc">
+    <h1 id="toc-title">Table Of Contents</h1>
+  </nav>
+  <nav epub:type = "landmarks">
+    <ol>
+      <li><a epub:type="bodymatter" href="chapter_1.html">1. Chapter 1</a></li>
+    </ol>
+  </nav>
+</body>"#;
+
+    #[test]
+    fn test_landmarks_replaced() {
+        let adjustments = PackageAdjustments {
+            landmarks: vec![
+                Landmark::new("toc", "nav.xhtml#toc", "Table Of Contents"),
+                Landmark::new("bodymatter", "chapter_1.html", "1. Chapter 1"),
+                Landmark::new("backmatter", "appendix.html", "Appendix & more"),
+            ],
+            ..Default::default()
+        };
+        let nav = adjustments.apply_to_nav(NAV);
+        assert!(nav.contains(
+            "<nav epub:type = \"landmarks\">\n    <ol>\n      <li><a epub:type=\"toc\" href=\"nav.xhtml#toc\">Table Of Contents</a></li>\n"
+        ));
+        assert!(nav.contains(
+            "<li><a epub:type=\"backmatter\" href=\"appendix.html\">Appendix &amp; more</a></li>\n    </ol>\n  </nav>\n</body>"
+        ));
+        assert_eq!(nav.matches("bodymatter").count(), 1);
+        assert!(nav.contains("<h1 id=\"toc-title\">Table Of Contents</h1>"));
+    }
+
+    #[test]
+    fn test_nav_without_landmarks_untouched() {
+        let adjustments = PackageAdjustments {
+            landmarks: vec![Landmark::new("toc", "nav.xhtml#toc", "Table Of Contents")],
+            ..Default::default()
+        };
+        let nav = "<body>\n  <div id=\"toc\"></div>\n</body>";
+        assert_eq!(adjustments.apply_to_nav(nav), nav);
+    }
+
+    #[test]
+    fn test_manifest_properties_set() {
+        let adjustments = PackageAdjustments {
+            manifest_properties: vec![("cover.html".to_string(), "svg")],
+            ..Default::default()
+        };
+        let opf = "<item media-type=\"image/png\" properties=\"cover-image\" id=\"cover-image\" href=\"cover.png\"/>\n<item media-type=\"application/xhtml+xml\" id=\"cover_html\" href=\"cover.html\"/>\n<guide>\n</guide>";
+        let opf = adjustments.apply_to_opf(opf);
+        assert!(opf.contains("<item properties=\"svg\" media-type=\"application/xhtml+xml\" id=\"cover_html\" href=\"cover.html\"/>"));
+        assert!(opf.contains("<item media-type=\"image/png\" properties=\"cover-image\""));
+    }
+
+    #[test]
+    fn test_guide_references_appended() {
+        let adjustments = PackageAdjustments {
+            guide: vec![Landmark::new("other.backmatter", "credits.html", "Credits")],
+            ..Default::default()
+        };
+        let opf = "  <guide>\n    <reference type=\"toc\" title=\"Table Of Contents\" href=\"nav.xhtml\"/>\n  </guide>\n";
+        assert_eq!(
+            adjustments.apply_to_opf(opf),
+            "  <guide>\n    <reference type=\"toc\" title=\"Table Of Contents\" href=\"nav.xhtml\"/>\n    <reference type=\"other.backmatter\" title=\"Credits\" href=\"credits.html\"/>\n  </guide>\n"
+        );
+    }
+}
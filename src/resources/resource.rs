@@ -3,13 +3,15 @@ use std::path::MAIN_SEPARATOR_STR;
 
 use const_format::concatcp;
 use html_parser::{Dom, Element, Node};
-use mdbook_core::book::BookItem;
 use mdbook_renderer::RenderContext;
-use pulldown_cmark::{Event, Tag};
+use pulldown_cmark::{Event, Options, Tag};
 use tracing::{debug, trace, warn};
 use url::Url;
 
+use crate::filters::admonition;
+use crate::front_matter::FrontMatter;
 use crate::resources::asset::{Asset, AssetKind};
+use crate::selection::ChapterSelection;
 use crate::{Error, path_io, utils};
 
 // Internal constants for reveling 'upper folder' paths in resource links inside MD
@@ -30,8 +32,12 @@ pub(crate) const UPPER_FOLDER_PATHS: &[&str] =
     &["/", MAIN_SEPARATOR_STR, UPPER_PARENT, UPPER_PARENT_LINUX];
 
 /// Find all resources in book and put them into HashMap.
-/// The key is a link, value is a composed Asset
-pub(crate) fn find(ctx: &RenderContext) -> Result<HashMap<String, Asset>, Error> {
+/// The key is a link, value is a composed Asset. Chapters not selected for the EPUB are skipped.
+pub(crate) fn find(
+    ctx: &RenderContext,
+    selection: &ChapterSelection,
+    markdown_options: Options,
+) -> Result<HashMap<String, Asset>, Error> {
     let mut assets: HashMap<String, Asset> = HashMap::new();
     debug!("Finding resources by:\n{:?}", ctx.config);
     let src_dir = path_io(
@@ -44,82 +50,79 @@ pub(crate) fn find(ctx: &RenderContext) -> Result<HashMap<String, Asset>, Error>
         ctx.book.items.len(),
         src_dir
     );
-    for section in ctx.book.iter() {
-        match *section {
-            BookItem::Chapter(ref ch) => {
-                let mut assets_count = 0;
-                debug!("Searching links and assets for: '{}'", ch);
-                if ch.path.is_none() {
-                    debug!("'{}' is a draft chapter and should be no content.", ch.name);
+    for selected in selection.chapters(&ctx.book) {
+        let ch = selected.chapter;
+        let mut assets_count = 0;
+        debug!("Searching links and assets for: '{}'", ch);
+        if ch.path.is_none() {
+            debug!("'{}' is a draft chapter and should be no content.", ch.name);
+            continue;
+        }
+        let (front_matter, content) = FrontMatter::split(&ch.content)?;
+        if front_matter.exclude {
+            debug!("'{}' is excluded by its front matter.", ch.name);
+            continue;
+        }
+        for link in find_assets_in_markdown(content, markdown_options)? {
+            debug!("'{}' finding Asset...", &link);
+            let asset = if let Ok(url) = Url::parse(&link) {
+                Asset::from_url(&link, url, &ctx.destination)
+            } else {
+                let result = Asset::from_local(&link, &src_dir, ch.path.as_ref().unwrap());
+                if let Err(Error::AssetOutsideSrcDir(_)) = result {
+                    warn!("Asset '{link}' is outside source dir '{src_dir:?}' and ignored");
                     continue;
-                }
-                for link in find_assets_in_markdown(&ch.content)? {
-                    debug!("'{}' finding Asset...", &link);
-                    let asset = if let Ok(url) = Url::parse(&link) {
-                        Asset::from_url(&link, url, &ctx.destination)
-                    } else {
-                        let result = Asset::from_local(&link, &src_dir, ch.path.as_ref().unwrap());
-                        if let Err(Error::AssetOutsideSrcDir(_)) = result {
-                            warn!("Asset '{link}' is outside source dir '{src_dir:?}' and ignored");
-                            continue;
-                        };
-                        result
-                    }?;
-
-                    // that is CORRECT generation way
-                    debug!(
-                        "Check relative path assets chapter: '{}' for\n{}",
-                        ch.name, asset
-                    );
-                    match asset.source {
-                        // local asset kind
-                        AssetKind::Local(_) => {
-                            let relative = asset.location_on_disk.strip_prefix(&src_dir);
-                            match relative {
-                                Ok(_relative_link_path) => {
-                                    let link_key = asset.original_link.clone();
-                                    if let std::collections::hash_map::Entry::Vacant(e) =
-                                        assets.entry(link_key.to_owned())
-                                    {
-                                        debug!(
-                                            "Adding asset by link '{:?}' : {}",
-                                            link_key, &asset
-                                        );
-                                        e.insert(asset);
-                                        assets_count += 1;
-                                    } else {
-                                        debug!("Skipped asset for '{}'", link_key);
-                                    }
-                                }
-                                _ => {
-                                    // skip incorrect resource/image link outside of book /SRC/ folder
-                                    warn!(
-                                        "Sorry, we can't add 'Local asset' that is outside of book's /src/ folder, {:?}",
-                                        &asset
-                                    );
-                                }
-                            }
-                        }
-                        AssetKind::Remote(_) => {
-                            // remote asset kind
+                };
+                result
+            }?;
+
+            // that is CORRECT generation way
+            debug!(
+                "Check relative path assets chapter: '{}' for\n{}",
+                ch.name, asset
+            );
+            match asset.source {
+                // local asset kind
+                AssetKind::Local(_) => {
+                    let relative = asset.location_on_disk.strip_prefix(&src_dir);
+                    match relative {
+                        Ok(_relative_link_path) => {
                             let link_key = asset.original_link.clone();
-                            debug!("Adding Remote asset by link '{}' : {}", link_key, &asset);
-                            assets.insert(link_key, asset);
-                            assets_count += 1;
+                            if let std::collections::hash_map::Entry::Vacant(e) =
+                                assets.entry(link_key.to_owned())
+                            {
+                                debug!("Adding asset by link '{:?}' : {}", link_key, &asset);
+                                e.insert(asset);
+                                assets_count += 1;
+                            } else {
+                                debug!("Skipped asset for '{}'", link_key);
+                            }
                         }
-                        AssetKind::Embedded => {
-                            debug!("Embedded asset '{}' by Event", &asset.original_link);
+                        _ => {
+                            // skip incorrect resource/image link outside of book /SRC/ folder
+                            warn!(
+                                "Sorry, we can't add 'Local asset' that is outside of book's /src/ folder, {:?}",
+                                &asset
+                            );
                         }
-                    };
+                    }
                 }
-                debug!(
-                    "Found '{}' links and assets inside '{}'",
-                    assets_count, ch.name
-                );
-            }
-            BookItem::Separator => trace!("Skip separator."),
-            BookItem::PartTitle(ref title) => trace!("Skip part title: {}.", title),
+                AssetKind::Remote(_) => {
+                    // remote asset kind
+                    let link_key = asset.original_link.clone();
+                    debug!("Adding Remote asset by link '{}' : {}", link_key, &asset);
+                    assets.insert(link_key, asset);
+                    assets_count += 1;
+                }
+                AssetKind::Embedded => {
+                    debug!("Embedded asset '{}' by Event", &asset.original_link);
+                }
+            };
         }
+        debug!(
+            "Found '{}' links and assets inside '{}'",
+            assets_count, ch.name
+        );
     }
     debug!("Added '{}' links and assets in total", assets.len());
     Ok(assets)
@@ -146,10 +149,16 @@ fn find_assets_in_nested_html_tags(element: &Element) -> Result<Vec<String>, Err
 }
 
 // Look up resources in chapter md content
-fn find_assets_in_markdown(chapter_src_content: &str) -> Result<Vec<String>, Error> {
+fn find_assets_in_markdown(
+    chapter_src_content: &str,
+    markdown_options: Options,
+) -> Result<Vec<String>, Error> {
     let mut found_asset = Vec::new();
 
-    let pull_down_parser = utils::create_new_pull_down_parser(chapter_src_content);
+    // the content of admonish blocks is Markdown too
+    let chapter_src_content = admonition::expand_admonish_blocks(chapter_src_content, false);
+    let pull_down_parser =
+        utils::create_new_pull_down_parser(&chapter_src_content, markdown_options);
     // that will process chapter content and find assets
     for event in pull_down_parser {
         match event {
@@ -192,6 +201,7 @@ fn find_assets_in_markdown(chapter_src_content: &str) -> Result<Vec<String>, Err
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Config;
     use serde_json::{Value, json};
     use std::path::{Path, PathBuf};
     use tempfile::TempDir;
@@ -219,7 +229,7 @@ mod tests {
             parent_dir.join("reddit.svg").canonicalize().unwrap(),
         ];
 
-        let got = find_assets_in_markdown(src)
+        let got = find_assets_in_markdown(src, Config::default().markdown_options())
             .unwrap()
             .into_iter()
             .map(|a| parent_dir.join(a).canonicalize().unwrap())
@@ -250,7 +260,12 @@ mod tests {
         }]);
         let ctx = ctx_with_chapters(&chapters, &dest_dir).unwrap();
 
-        let mut assets = find(&ctx).unwrap();
+        let mut assets = find(
+            &ctx,
+            &ChapterSelection::default(),
+            Config::default().markdown_options(),
+        )
+        .unwrap();
         assert_eq!(2, assets.len());
 
         fn assert_asset(a: Asset, link: &str, ctx: &RenderContext) {
@@ -295,7 +310,12 @@ mod tests {
             "parent_names": []}}]);
         let ctx = ctx_with_chapters(&chapters, &dest_dir).unwrap();
 
-        let mut assets = find(&ctx).unwrap();
+        let mut assets = find(
+            &ctx,
+            &ChapterSelection::default(),
+            Config::default().markdown_options(),
+        )
+        .unwrap();
         assert_eq!(2, assets.len());
 
         for (key, value) in assets.clone().into_iter() {
@@ -337,7 +357,51 @@ mod tests {
             "path": null,
             "parent_names": []}}]);
         let ctx = ctx_with_chapters(&chapters, &dest_dir).unwrap();
-        assert!(find(&ctx).unwrap().is_empty());
+        assert!(
+            find(
+                &ctx,
+                &ChapterSelection::default(),
+                Config::default().markdown_options()
+            )
+            .unwrap()
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_find_skips_excluded_chapter() {
+        let tmp_dir = TempDir::new().unwrap();
+        let temp = tmp_dir.path().join("mdbook-epub");
+        let dest_dir = temp.as_path().to_string_lossy().to_string();
+        let chapters = json!([
+        {"Chapter": {
+            "name": "Chapter 1",
+            "content": "# Chapter 1\r\n\r\n![Image](./rust-logo.png)",
+            "number": [1],
+            "sub_items": [],
+            "path": "chapter_1.md",
+            "parent_names": []}}]);
+        let ctx = ctx_with_chapters(&chapters, &dest_dir).unwrap();
+        let config = crate::Config {
+            exclude: vec!["chapter_*.md".to_string()],
+            ..Default::default()
+        };
+        let selection = ChapterSelection::from_config(&config).unwrap();
+        assert!(
+            find(&ctx, &selection, Config::default().markdown_options())
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            find(
+                &ctx,
+                &ChapterSelection::default(),
+                Config::default().markdown_options()
+            )
+            .unwrap()
+            .len(),
+            1
+        );
     }
 
     #[test]
@@ -321,8 +321,8 @@ mod tests {
             }
         }
         let cr = TestHandler {};
-        let mut a = temp_remote_asset("https://mdbook-epub.org/not-exist.svg").unwrap();
-        let r = cr.download(&mut a);
+        let a = temp_remote_asset("https://mdbook-epub.org/not-exist.svg").unwrap();
+        let r = cr.download(&a);
 
         assert!(r.is_err());
         assert!(matches!(r.unwrap_err(), Error::AssetFileNotFound(_)));
@@ -343,8 +343,8 @@ mod tests {
             }
         }
         let cr = TestHandler {};
-        let mut a = temp_remote_asset("https://mdbook-epub.org/bad.svg").unwrap();
-        let r = cr.download(&mut a);
+        let a = temp_remote_asset("https://mdbook-epub.org/bad.svg").unwrap();
+        let r = cr.download(&a);
         trace!("{:?}", &r);
 
         panic!("{}", r.unwrap_err().to_string());
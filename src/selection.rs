@@ -0,0 +1,300 @@
+use crate::front_matter::ChapterType;
+use crate::{Config, Error};
+use glob::{MatchOptions, Pattern};
+use mdbook_core::book::{Book, BookItem, Chapter, SectionNumber};
+use std::path::Path;
+use tracing::debug;
+
+pub(crate) const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A chapter picked for the EPUB, together with the section number it is shown with.
+#[derive(Debug, Clone)]
+pub(crate) struct SelectedChapter<'b> {
+    pub chapter: &'b Chapter,
+    pub number: Option<SectionNumber>,
+    /// `Frontmatter` for the prefix chapters of `SUMMARY.md` and `Backmatter` for
+    /// the suffix chapters.
+    pub matter: Option<ChapterType>,
+}
+
+/// Decides which chapters make it into the EPUB, based on the `include` and
+/// `exclude` glob lists. Patterns are matched against the chapter's path relative
+/// to the book's source directory, e.g. `exercises/**` or `**/playground.md`.
+#[derive(Debug, Default)]
+pub(crate) struct ChapterSelection {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    renumber: bool,
+}
+
+impl ChapterSelection {
+    pub(crate) fn from_config(config: &Config) -> Result<Self, Error> {
+        Ok(ChapterSelection {
+            include: compile_patterns(&config.include)?,
+            exclude: compile_patterns(&config.exclude)?,
+            renumber: config.renumber_chapters,
+        })
+    }
+
+    /// Check whether the chapter itself passes the `include`/`exclude` lists.
+    /// Draft chapters have no path, so they are only kept when there is no
+    /// `include` list.
+    pub(crate) fn is_selected(&self, ch: &Chapter) -> bool {
+        match ch.path {
+            Some(ref path) => {
+                let path = chapter_path(path);
+                let included = self.include.is_empty()
+                    || self
+                        .include
+                        .iter()
+                        .any(|p| p.matches_with(&path, MATCH_OPTIONS));
+                included
+                    && !self
+                        .exclude
+                        .iter()
+                        .any(|p| p.matches_with(&path, MATCH_OPTIONS))
+            }
+            None => self.include.is_empty(),
+        }
+    }
+
+    /// Collect the selected chapters in reading order. Skipping a chapter also
+    /// skips all of its sub-chapters.
+    pub(crate) fn chapters<'b>(&self, book: &'b Book) -> Vec<SelectedChapter<'b>> {
+        // prefix and suffix chapters are the unnumbered ones around the numbered chapters
+        let numbered = book
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| matches!(item, BookItem::Chapter(ch) if ch.number.is_some()))
+            .map(|(idx, _)| idx);
+        let bounds = numbered.clone().min().zip(numbered.max());
+
+        let mut selected = Vec::new();
+        self.collect(
+            &book.items,
+            &SectionNumber::default(),
+            bounds,
+            &mut selected,
+        );
+        selected
+    }
+
+    fn collect<'b>(
+        &self,
+        items: &'b [BookItem],
+        parent_number: &SectionNumber,
+        numbered_bounds: Option<(usize, usize)>,
+        selected: &mut Vec<SelectedChapter<'b>>,
+    ) {
+        let mut counter = 0;
+        for (idx, item) in items.iter().enumerate() {
+            let BookItem::Chapter(ch) = item else {
+                continue;
+            };
+            if !self.is_selected(ch) {
+                debug!("Chapter '{}' is not selected for the EPUB", ch.name);
+                continue;
+            }
+
+            let number = if self.renumber {
+                ch.number.as_ref().map(|_| {
+                    counter += 1;
+                    let mut number = parent_number.clone();
+                    number.push(counter);
+                    number
+                })
+            } else {
+                ch.number.clone()
+            };
+            let matter = match numbered_bounds {
+                Some((first, _)) if ch.number.is_none() && idx < first => {
+                    Some(ChapterType::Frontmatter)
+                }
+                Some((_, last)) if ch.number.is_none() && idx > last => {
+                    Some(ChapterType::Backmatter)
+                }
+                _ => None,
+            };
+            let sub_parent_number = number.clone().unwrap_or_default();
+            selected.push(SelectedChapter {
+                chapter: ch,
+                number,
+                matter,
+            });
+            self.collect(&ch.sub_items, &sub_parent_number, None, selected);
+        }
+    }
+}
+
+pub(crate) fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>, Error> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).map_err(|e| Error::GlobPattern(p.clone(), e)))
+        .collect()
+}
+
+/// Chapter path with forward slashes, so the same patterns work on every platform.
+pub(crate) fn chapter_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn book() -> Book {
+        let chapter = |name: &str, number: Option<Vec<u32>>, path: &str, sub_items| {
+            json!({"Chapter": {
+                "name": name,
+                "content": "",
+                "number": number,
+                "sub_items": sub_items,
+                "path": path,
+                "parent_names": []
+            }})
+        };
+        let items = json!([
+            chapter("Intro", None, "intro.md", json!([])),
+            chapter(
+                "Basics",
+                Some(vec![1]),
+                "basics/index.md",
+                json!([
+                    chapter("Syntax", Some(vec![1, 1]), "basics/syntax.md", json!([])),
+                    chapter(
+                        "Playground",
+                        Some(vec![1, 2]),
+                        "basics/playground.md",
+                        json!([])
+                    ),
+                    chapter("Types", Some(vec![1, 3]), "basics/types.md", json!([])),
+                ])
+            ),
+            chapter(
+                "Exercises",
+                Some(vec![2]),
+                "exercises/index.md",
+                json!([chapter(
+                    "Ex 1",
+                    Some(vec![2, 1]),
+                    "exercises/one.md",
+                    json!([])
+                )])
+            ),
+            chapter("Advanced", Some(vec![3]), "advanced.md", json!([])),
+            chapter("Credits", None, "credits.md", json!([])),
+        ]);
+        serde_json::from_value(json!({"items": items, "__non_exhaustive": null})).unwrap()
+    }
+
+    fn selection(include: &[&str], exclude: &[&str], renumber: bool) -> ChapterSelection {
+        let config = Config {
+            include: include.iter().map(|s| s.to_string()).collect(),
+            exclude: exclude.iter().map(|s| s.to_string()).collect(),
+            renumber_chapters: renumber,
+            ..Default::default()
+        };
+        ChapterSelection::from_config(&config).unwrap()
+    }
+
+    fn titles(selected: &[SelectedChapter]) -> Vec<String> {
+        selected
+            .iter()
+            .map(|s| match s.number {
+                Some(ref n) => format!("{n} {}", s.chapter.name),
+                None => s.chapter.name.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_select_everything_by_default() {
+        let book = book();
+        let selected = selection(&[], &[], false).chapters(&book);
+        assert_eq!(selected.len(), 9);
+    }
+
+    #[test]
+    fn test_exclude_keeps_numbering() {
+        let book = book();
+        let selected = selection(&[], &["**/playground.md", "exercises/**"], false).chapters(&book);
+        assert_eq!(
+            titles(&selected),
+            vec![
+                "Intro",
+                "1. Basics",
+                "1.1. Syntax",
+                "1.3. Types",
+                "3. Advanced",
+                "Credits"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_with_renumbering() {
+        let book = book();
+        let selected = selection(&[], &["**/playground.md", "exercises/**"], true).chapters(&book);
+        assert_eq!(
+            titles(&selected),
+            vec![
+                "Intro",
+                "1. Basics",
+                "1.1. Syntax",
+                "1.2. Types",
+                "2. Advanced",
+                "Credits"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_include_only() {
+        let book = book();
+        let selected = selection(&["basics/*", "advanced.md"], &[], false).chapters(&book);
+        assert_eq!(
+            titles(&selected),
+            vec![
+                "1. Basics",
+                "1.1. Syntax",
+                "1.2. Playground",
+                "1.3. Types",
+                "3. Advanced"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prefix_and_suffix_chapters() {
+        let book = book();
+        let selected = selection(&[], &[], false).chapters(&book);
+        let matter = |name: &str| {
+            selected
+                .iter()
+                .find(|s| s.chapter.name == name)
+                .and_then(|s| s.matter)
+        };
+        assert_eq!(matter("Intro"), Some(ChapterType::Frontmatter));
+        assert_eq!(matter("Basics"), None);
+        assert_eq!(matter("Syntax"), None);
+        assert_eq!(matter("Credits"), Some(ChapterType::Backmatter));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let config = Config {
+            exclude: vec!["[".to_string()],
+            ..Default::default()
+        };
+        assert!(matches!(
+            ChapterSelection::from_config(&config),
+            Err(Error::GlobPattern(..))
+        ));
+    }
+}
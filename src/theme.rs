@@ -0,0 +1,271 @@
+use crate::Error;
+use crate::filters::heading::id_from_content;
+use crate::selection::{MATCH_OPTIONS, chapter_path, compile_patterns};
+use chrono::{DateTime, Local, NaiveDate};
+use glob::Pattern;
+use handlebars::{
+    Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+    RenderErrorReason, handlebars_helper,
+};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Format used by the `date` helper when the template doesn't give one.
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Use a different template for the chapters matching a glob pattern.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterTemplate {
+    /// Glob pattern of chapters, relative to the book's `src` dir.
+    pub pattern: String,
+    /// The template file (relative to the book root).
+    pub template: PathBuf,
+}
+
+/// The templates picked for chapters by their path, the first matching pattern
+/// winning.
+#[derive(Debug, Default)]
+pub(crate) struct ChapterTemplates {
+    templates: Vec<(Pattern, PathBuf)>,
+}
+
+impl ChapterTemplates {
+    pub(crate) fn new(templates: &[ChapterTemplate]) -> Result<Self, Error> {
+        let patterns: Vec<String> = templates.iter().map(|t| t.pattern.clone()).collect();
+        let templates = compile_patterns(&patterns)?
+            .into_iter()
+            .zip(templates.iter().map(|t| t.template.clone()))
+            .collect();
+        Ok(ChapterTemplates { templates })
+    }
+
+    /// The template for the chapter at the path, relative to the book's `src` dir.
+    pub(crate) fn find(&self, chapter: &Path) -> Option<&Path> {
+        let path = chapter_path(chapter);
+        self.templates
+            .iter()
+            .find(|(pattern, _)| pattern.matches_with(&path, MATCH_OPTIONS))
+            .map(|(_, template)| template.as_path())
+    }
+}
+
+/// Register every `*.hbs` file in the theme directory as a partial, named after its
+/// path relative to the directory without the extension, e.g. `{{> header}}` or
+/// `{{> parts/footer}}`.
+pub(crate) fn register_partials(hbs: &mut Handlebars, theme_dir: &Path) -> Result<(), Error> {
+    if !theme_dir.is_dir() {
+        return Err(Error::ThemeDir(theme_dir.to_path_buf()));
+    }
+    // the theme dir may contain glob characters like `[`
+    let pattern = format!(
+        "{}/**/*.hbs",
+        glob::Pattern::escape(&theme_dir.to_string_lossy())
+    );
+    let files = glob::glob(&pattern).map_err(|e| Error::GlobPattern(pattern.clone(), e))?;
+    for file in files.filter_map(Result::ok) {
+        let name = file
+            .strip_prefix(theme_dir)?
+            .with_extension("")
+            .to_string_lossy()
+            .replace('\\', "/");
+        debug!("Registering partial '{}' from {}", name, file.display());
+        let template =
+            std::fs::read_to_string(&file).map_err(|_| Error::OpenTemplate(file.clone()))?;
+        hbs.register_partial(&name, template)
+            .map_err(|e| Error::template_parse(file.display().to_string(), e))?;
+    }
+    Ok(())
+}
+
+/// Register the helpers available to all templates.
+pub(crate) fn register_helpers(hbs: &mut Handlebars) {
+    hbs.register_helper("slugify", Box::new(slugify));
+    hbs.register_helper("date", Box::new(date));
+    hbs.register_helper("asset", Box::new(asset));
+}
+
+// `{{slugify "Some Title"}}` gives the anchor id a heading with that text gets
+handlebars_helper!(slugify: |text: str| id_from_content(text));
+
+/// `{{date}}` gives today's date, `{{date "2024-05-01" format="%B %Y"}}` formats
+/// the given date (`YYYY-MM-DD` or RFC 3339) with a `strftime` format.
+fn date(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let format = match h.hash_get("format") {
+        Some(format) => format
+            .value()
+            .as_str()
+            .ok_or_else(|| RenderErrorReason::InvalidParamType("string"))?,
+        None => DEFAULT_DATE_FORMAT,
+    };
+    let items = chrono::format::StrftimeItems::new(format)
+        .parse()
+        .map_err(|_| helper_error(format!("invalid date format '{format}'")))?;
+    let date = match h.param(0).map(|p| p.value()) {
+        None => Local::now().fixed_offset(),
+        Some(value) => {
+            let value = value
+                .as_str()
+                .ok_or_else(|| RenderErrorReason::InvalidParamType("string"))?;
+            parse_date(value).ok_or_else(|| helper_error(format!("invalid date '{value}'")))?
+        }
+    };
+    out.write(&date.format_with_items(items.iter()).to_string())?;
+    Ok(())
+}
+
+fn parse_date(value: &str) -> Option<DateTime<chrono::FixedOffset>> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().fixed_offset())
+}
+
+/// `{{asset "images/logo.png"}}` gives the link to a file from the book's `src`
+/// dir, relative to the page being rendered.
+fn asset(
+    h: &Helper,
+    _: &Handlebars,
+    ctx: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let path = h
+        .param(0)
+        .and_then(|p| p.value().as_str())
+        .ok_or_else(|| RenderErrorReason::ParamNotFoundForIndex("asset", 0))?;
+    let path_to_root = ctx
+        .data()
+        .get("path_to_root")
+        .and_then(|p| p.as_str())
+        .unwrap_or_default();
+    out.write(&format!("{}{}", path_to_root, path.trim_start_matches('/')))?;
+    Ok(())
+}
+
+fn helper_error(message: String) -> RenderError {
+    RenderError::from(RenderErrorReason::Other(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, data: serde_json::Value) -> Result<String, RenderError> {
+        let mut hbs = Handlebars::new();
+        register_helpers(&mut hbs);
+        hbs.render_template(template, &data)
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(
+            render("{{slugify title}}", json!({"title": "Getting Started!"})).unwrap(),
+            "getting-started"
+        );
+    }
+
+    #[test]
+    fn test_date() {
+        assert_eq!(
+            render("{{date \"2024-05-01\"}}", json!({})).unwrap(),
+            "2024-05-01"
+        );
+        assert_eq!(
+            render(
+                "{{date published format=\"%d %B %Y\"}}",
+                json!({"published": "2024-05-01T10:00:00+02:00"})
+            )
+            .unwrap(),
+            "01 May 2024"
+        );
+        assert_eq!(
+            render("{{date format=\"%Y\"}}", json!({})).unwrap().len(),
+            4
+        );
+        assert!(render("{{date \"May 1st\"}}", json!({})).is_err());
+        assert!(render("{{date format=\"%Q\"}}", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_asset() {
+        assert_eq!(
+            render(
+                "{{asset \"images/logo.png\"}}",
+                json!({"path_to_root": "../../"})
+            )
+            .unwrap(),
+            "../../images/logo.png"
+        );
+        assert_eq!(
+            render("{{asset \"/images/logo.png\"}}", json!({})).unwrap(),
+            "images/logo.png"
+        );
+    }
+
+    #[test]
+    fn test_chapter_templates() {
+        let templates = ChapterTemplates::new(&[
+            ChapterTemplate {
+                pattern: "appendix/**".to_string(),
+                template: PathBuf::from("appendix.hbs"),
+            },
+            ChapterTemplate {
+                pattern: "**/*.md".to_string(),
+                template: PathBuf::from("chapter.hbs"),
+            },
+        ])
+        .unwrap();
+        assert_eq!(
+            templates.find(Path::new("appendix/glossary.md")),
+            Some(Path::new("appendix.hbs"))
+        );
+        assert_eq!(
+            templates.find(Path::new("intro.md")),
+            Some(Path::new("chapter.hbs"))
+        );
+        assert_eq!(templates.find(Path::new("notes.txt")), None);
+    }
+
+    #[test]
+    fn test_register_partials() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(tmp_dir.path().join("parts")).unwrap();
+        std::fs::write(tmp_dir.path().join("header.hbs"), "<h1>{{ title }}</h1>").unwrap();
+        std::fs::write(tmp_dir.path().join("parts/footer.hbs"), "<p>The end</p>").unwrap();
+        std::fs::write(tmp_dir.path().join("notes.txt"), "{{").unwrap();
+
+        let mut hbs = Handlebars::new();
+        register_partials(&mut hbs, tmp_dir.path()).unwrap();
+        assert_eq!(
+            hbs.render_template("{{> header}}{{> parts/footer}}", &json!({"title": "Hi"}))
+                .unwrap(),
+            "<h1>Hi</h1><p>The end</p>"
+        );
+        assert!(register_partials(&mut hbs, &tmp_dir.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_register_partials_with_glob_characters_in_path() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let theme_dir = tmp_dir.path().join("theme [draft]*?");
+        std::fs::create_dir(&theme_dir).unwrap();
+        std::fs::write(theme_dir.join("header.hbs"), "<h1>{{ title }}</h1>").unwrap();
+
+        let mut hbs = Handlebars::new();
+        register_partials(&mut hbs, &theme_dir).unwrap();
+        assert_eq!(
+            hbs.render_template("{{> header}}", &json!({"title": "Hi"}))
+                .unwrap(),
+            "<h1>Hi</h1>"
+        );
+    }
+}
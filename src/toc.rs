@@ -0,0 +1,174 @@
+use crate::filters::heading::Heading;
+use epub_builder::TocElement;
+use mdbook_core::utils::{escape_html, escape_html_attribute};
+use std::fmt::Write as _;
+
+/// Where the in-book table of contents page is put in the EPUB.
+pub(crate) const INLINE_TOC_PATH: &str = "toc.html";
+
+/// A line of the in-book table of contents.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TocEntry {
+    pub href: String,
+    pub title: String,
+    /// Nesting level, starting with 0 for top-level chapters.
+    pub level: usize,
+}
+
+impl TocEntry {
+    pub(crate) fn new(href: impl Into<String>, title: impl Into<String>, level: usize) -> Self {
+        TocEntry {
+            href: href.into().replace('\\', "/"),
+            title: title.into(),
+            level,
+        }
+    }
+}
+
+/// Navigation points for the headings of a chapter, from `h2` down to the
+/// `depth` heading level, with lower level headings nested under higher ones.
+pub(crate) fn heading_toc_elements(
+    path: &str,
+    headings: &[Heading],
+    depth: usize,
+) -> Vec<TocElement> {
+    let path = path.replace('\\', "/");
+    // the chapter itself stands for its `h1` headings
+    let mut chapter = TocElement::new(path.as_str(), "");
+    for heading in headings {
+        let level = heading.level as usize;
+        if (2..=depth).contains(&level) {
+            chapter.add(
+                TocElement::new(format!("{}#{}", path, heading.id), heading.title.as_str())
+                    .level(level as i32),
+            );
+        }
+    }
+    chapter.children
+}
+
+/// Render the entries as nested ordered lists. An entry can be at most one level
+/// deeper than the one before it, deeper ones are moved up.
+pub(crate) fn render_toc_list(entries: &[TocEntry]) -> String {
+    let mut html = String::new();
+    // one item per open list, telling whether it has an open list item
+    let mut open_lists: Vec<bool> = Vec::new();
+    for entry in entries {
+        let level = entry.level.min(open_lists.len());
+        while open_lists.len() > level + 1 {
+            close_list(&mut html, &mut open_lists);
+        }
+        if open_lists.len() == level + 1 {
+            if open_lists[level] {
+                html.push_str("</li>\n");
+            }
+        } else if open_lists.is_empty() {
+            html.push_str("<ol class=\"toc\">\n");
+            open_lists.push(false);
+        } else {
+            html.push_str("\n<ol>\n");
+            open_lists.push(false);
+        }
+        write!(
+            html,
+            "<li><a href=\"{}\">{}</a>",
+            escape_html_attribute(&entry.href),
+            escape_html(&entry.title)
+        )
+        .unwrap();
+        open_lists[level] = true;
+    }
+    while !open_lists.is_empty() {
+        close_list(&mut html, &mut open_lists);
+    }
+    html
+}
+
+fn close_list(html: &mut String, open_lists: &mut Vec<bool>) {
+    if open_lists.pop() == Some(true) {
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ol>");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flat_list() {
+        let entries = vec![
+            TocEntry::new("intro.html", "Intro", 0),
+            TocEntry::new("usage.html", "Usage & more", 0),
+        ];
+        assert_eq!(
+            render_toc_list(&entries),
+            "<ol class=\"toc\">\n<li><a href=\"intro.html\">Intro</a></li>\n<li><a href=\"usage.html\">Usage &amp; more</a></li>\n</ol>"
+        );
+    }
+
+    #[test]
+    fn test_nested_list() {
+        let entries = vec![
+            TocEntry::new("a.html", "1. A", 0),
+            TocEntry::new("a\\b.html", "1.1. B", 1),
+            TocEntry::new("a\\b.html#detail", "Detail", 2),
+            TocEntry::new("c.html", "2. C", 0),
+        ];
+        assert_eq!(
+            render_toc_list(&entries),
+            "<ol class=\"toc\">\n<li><a href=\"a.html\">1. A</a>\n<ol>\n<li><a href=\"a/b.html\">1.1. B</a>\n<ol>\n<li><a href=\"a/b.html#detail\">Detail</a></li>\n</ol></li>\n</ol></li>\n<li><a href=\"c.html\">2. C</a></li>\n</ol>"
+        );
+    }
+
+    #[test]
+    fn test_level_jump_is_moved_up() {
+        let entries = vec![
+            TocEntry::new("a.html", "A", 0),
+            TocEntry::new("a.html#deep", "Deep", 3),
+        ];
+        assert_eq!(
+            render_toc_list(&entries),
+            "<ol class=\"toc\">\n<li><a href=\"a.html\">A</a>\n<ol>\n<li><a href=\"a.html#deep\">Deep</a></li>\n</ol></li>\n</ol>"
+        );
+    }
+
+    #[test]
+    fn test_heading_toc_elements() {
+        use pulldown_cmark::HeadingLevel;
+        let heading = |level, id: &str| Heading {
+            level,
+            id: id.to_string(),
+            title: id.to_uppercase(),
+        };
+        let headings = vec![
+            heading(HeadingLevel::H1, "intro"),
+            heading(HeadingLevel::H2, "setup"),
+            heading(HeadingLevel::H3, "linux"),
+            heading(HeadingLevel::H4, "debian"),
+            heading(HeadingLevel::H2, "usage"),
+        ];
+
+        let elements = heading_toc_elements("guide\\intro.html", &headings, 3);
+        assert_eq!(elements.len(), 2);
+        assert_eq!(elements[0].url, "guide/intro.html#setup");
+        assert_eq!(elements[0].title, "SETUP");
+        assert_eq!(elements[0].children.len(), 1);
+        assert_eq!(elements[0].children[0].url, "guide/intro.html#linux");
+        assert!(elements[0].children[0].children.is_empty());
+        assert_eq!(elements[1].url, "guide/intro.html#usage");
+
+        assert_eq!(
+            heading_toc_elements("intro.html", &headings, 2)[0]
+                .children
+                .len(),
+            0
+        );
+        assert!(heading_toc_elements("intro.html", &headings, 1).is_empty());
+    }
+
+    #[test]
+    fn test_empty_list() {
+        assert_eq!(render_toc_list(&[]), "");
+    }
+}
@@ -3,13 +3,18 @@ use std::ffi::OsStr;
 use std::path::{Component, Path, PathBuf};
 use url::Url;
 
-pub(crate) fn create_new_pull_down_parser(text: &str) -> Parser<'_> {
-    let mut opts = Options::empty();
-    opts.insert(Options::ENABLE_TABLES);
-    opts.insert(Options::ENABLE_FOOTNOTES);
-    opts.insert(Options::ENABLE_STRIKETHROUGH);
-    opts.insert(Options::ENABLE_TASKLISTS);
-    Parser::new_ext(text, opts)
+/// Create a Markdown parser with the options from `Config::markdown_options`, so
+/// finding assets and rendering see the same Markdown.
+pub(crate) fn create_new_pull_down_parser(text: &str, options: Options) -> Parser<'_> {
+    Parser::new_ext(text, options)
+}
+
+/// The language and attributes of a fenced code block's info string, e.g. `rust`,
+/// `ignore` and `edition2021` for `rust,ignore edition2021`. Like mdbook, they are
+/// separated by commas or whitespace.
+pub(crate) fn code_block_attributes(info: &str) -> impl Iterator<Item = &str> {
+    info.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|attribute| !attribute.is_empty())
 }
 
 // From cargo/util/paths.rs
@@ -65,6 +70,18 @@ pub(crate) fn hash_link(url: &Url) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_code_block_attributes() {
+        let attributes = |info| code_block_attributes(info).collect::<Vec<_>>();
+        assert_eq!(attributes("dot\tlinenos"), ["dot", "linenos"]);
+        assert_eq!(
+            attributes("rust, ignore edition2021"),
+            ["rust", "ignore", "edition2021"]
+        );
+        assert_eq!(attributes(" rust "), ["rust"]);
+        assert!(attributes("").is_empty());
+    }
+
     #[test]
     fn test_hash_named_url_with_extension() {
         let test_url = "https://www.rust-lang.org/static/images/rust-logo-blk.svg";
@@ -167,7 +184,7 @@ mod tests {
 "#;
         let source = source.to_string();
         let content = source.replace(
-            &"https://github.com/sunface/rust-course/blob/main/assets/studyrust公众号.png?raw=true",
+            "https://github.com/sunface/rust-course/blob/main/assets/studyrust公众号.png?raw=true",
             "b270cb6837d41f98.png",
         );
         println!("{}", &content);
@@ -0,0 +1,52 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_gfm_alerts_are_admonitions() {
+    let mut doc = generate_epub("admonition_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains(
+        "<aside class=\"admonition note\">\n<p class=\"admonition-title\">Note</p>\n<p>Useful information that users should know.</p>\n</aside>"
+    ));
+    assert!(content.contains(
+        "<aside class=\"admonition warning\">\n<p class=\"admonition-title\">Warning</p>\n<p>Critical content demanding <strong>immediate</strong> attention.</p>\n</aside>"
+    ));
+    assert!(content.contains("<blockquote>\n<p>An ordinary quote.</p>\n</blockquote>"));
+    assert!(!content.contains("[!NOTE]"));
+}
+
+#[test]
+#[serial]
+fn test_admonish_blocks_are_admonitions() {
+    let mut doc = generate_epub("admonition_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    assert!(content.contains(
+        "<aside class=\"admonition tip\">\n<p class=\"admonition-title\">Pro tip</p>\n<p>Admonish blocks hold <em>Markdown</em>, even images:</p>\n<p><img src=\"diagram.png\" alt=\"Diagram\" /></p>\n</aside>"
+    ));
+    assert!(doc.0.get_resource_by_path("OEBPS/diagram.png").is_some());
+    let stylesheet = doc
+        .0
+        .get_resource_str_by_path("OEBPS/stylesheet.css")
+        .unwrap();
+    assert!(stylesheet.contains(".admonition-title"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_admonition_book_is_valid() {
+    output_epub_is_valid("admonition_example");
+}
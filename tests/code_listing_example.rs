@@ -0,0 +1,46 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_linenos_code_block_is_numbered() {
+    let mut doc = generate_epub("code_listing_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains(
+        "<pre><code class=\"language-rust numbered\"><span class=\"line\">fn main() {</span>\n<span class=\"line\">    println!(\"{}\", 1 &lt; 2);</span>\n<span class=\"line\">}</span>\n</code></pre>"
+    ));
+}
+
+#[test]
+#[serial]
+fn test_long_code_lines_are_wrapped() {
+    let mut doc = generate_epub("code_listing_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    assert!(content.contains(
+        "<pre><code class=\"language-rust\"><span class=\"line\">let message = format!(\"{} is a rather \n<span class=\"continuation\">long line of code\", name);</span></span>\n</code></pre>"
+    ));
+    let css = doc
+        .0
+        .get_resource_str_by_path("OEBPS/stylesheet.css")
+        .unwrap();
+    assert!(css.contains(".continuation::before"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_code_listing_book_is_valid() {
+    output_epub_is_valid("code_listing_example");
+}
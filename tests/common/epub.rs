@@ -66,7 +66,7 @@ pub fn create_dummy_book(name: &str) -> Result<(RenderContext, MDBook, TempDir),
 
     let md = MDBook::load(dummy_book);
 
-    let book = md.expect(&format!("{:?} MDBook is not loaded", name));
+    let book = md.unwrap_or_else(|_| panic!("{:?} MDBook is not loaded", name));
     let ctx = RenderContext::new(
         book.root.clone(),
         book.book.clone(),
@@ -92,7 +92,7 @@ pub fn create_dummy_book_preserve_temp_folder(
 
     let md = MDBook::load(dummy_book);
 
-    let book = md.expect(&format!("{:?} MDBook is not loaded", name));
+    let book = md.unwrap_or_else(|_| panic!("{:?} MDBook is not loaded", name));
     let ctx = RenderContext::new(
         book.root.clone(),
         book.book.clone(),
@@ -119,11 +119,11 @@ pub fn epub_check(path: &Path) -> Result<(), Error> {
         debug!("File exists: {}", Path::new(&epubcheck_path).exists());
 
         Command::new("java")
-            .args(&["-jar", &epubcheck_path, path.to_str().unwrap()])
+            .args(["-jar", &epubcheck_path, path.to_str().unwrap()])
             .output()
     };
 
-    #[cfg(any(target_os = "macos"))]
+    #[cfg(target_os = "macos")]
     let cmd = Command::new("epubcheck").arg(path).output();
 
     match cmd {
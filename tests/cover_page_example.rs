@@ -0,0 +1,41 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_cover_page_shows_cover_image() {
+    let mut doc = generate_epub("cover_page_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let page = doc.0.get_resource_str_by_path("OEBPS/cover.html").unwrap();
+    debug!("cover page = {:?}", page);
+    assert!(page.contains("<body class=\"cover\" epub:type=\"cover\">"));
+    assert!(page.contains("viewBox=\"0 0 128 128\""));
+    assert!(page.contains("<image width=\"128\" height=\"128\" xlink:href=\"cover.png\"/>"));
+}
+
+#[test]
+#[serial]
+fn test_cover_page_comes_first() {
+    let mut doc = generate_epub("cover_page_example").unwrap();
+    let first = doc.0.spine.first().unwrap();
+    let resource = doc.0.resources.get(&first.idref).unwrap();
+    assert!(resource.path.ends_with("cover.html"));
+
+    let nav = doc.0.get_resource_str_by_path("OEBPS/nav.xhtml").unwrap();
+    assert!(nav.contains("<a epub:type=\"cover\" href=\"cover.html\">"));
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert!(opf.contains("<reference type=\"cover\""));
+    assert!(opf.contains("properties=\"svg\""));
+    assert!(opf.contains("properties=\"cover-image\""));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_cover_page_book_is_valid() {
+    output_epub_is_valid("cover_page_example");
+}
@@ -0,0 +1,65 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+/// The path of the image that took the place of the chapter's code block.
+fn diagram_src(content: &str) -> String {
+    let start = content
+        .find("<div class=\"diagram\"><img src=\"")
+        .expect("the code block should be rendered to an image");
+    let src = &content[start..];
+    let src = &src[src.find("src=\"").unwrap() + 5..];
+    src[..src.find('"').unwrap()].to_string()
+}
+
+#[test]
+#[serial]
+fn test_code_blocks_are_rendered_to_images() {
+    let mut doc = generate_epub("diagram_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    let src = diagram_src(&content);
+    assert!(src.starts_with("diagrams/") && src.ends_with(".svg"));
+    assert!(content.contains("alt=\"svg diagram\""));
+    assert!(!content.contains("&lt;svg"));
+    assert!(content.contains("<code class=\"language-rust\">fn main() {}"));
+
+    let image = doc
+        .0
+        .get_resource_str_by_path(format!("OEBPS/{src}"))
+        .unwrap();
+    assert!(image.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert!(opf.contains(&format!("href=\"{src}\"")));
+}
+
+#[test]
+#[serial]
+fn test_same_code_block_shares_the_image() {
+    let mut doc = generate_epub("diagram_example").unwrap();
+    let first = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    let second = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_2.html")
+        .unwrap();
+    assert_eq!(diagram_src(&first), diagram_src(&second));
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert_eq!(opf.matches(&diagram_src(&first)).count(), 1);
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_diagram_book_is_valid() {
+    output_epub_is_valid("diagram_example");
+}
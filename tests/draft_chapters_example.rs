@@ -0,0 +1,49 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_draft_chapters_get_placeholder_pages() {
+    let mut doc = generate_epub("draft_chapters_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let spine: Vec<String> = doc
+        .0
+        .spine
+        .iter()
+        .map(|item| {
+            let resource = doc.0.resources.get(&item.idref).unwrap();
+            resource.path.to_string_lossy().to_string()
+        })
+        .collect();
+    assert_eq!(
+        spine,
+        vec![
+            "OEBPS/chapter_1.html",
+            "OEBPS/draft-advanced-topics.html",
+            "OEBPS/macros.html",
+            "OEBPS/draft-advanced-topics-1.html"
+        ]
+    );
+
+    let placeholder = doc
+        .0
+        .get_resource_str_by_path("OEBPS/draft-advanced-topics.html")
+        .unwrap();
+    debug!("placeholder = {:?}", placeholder);
+    assert!(placeholder.contains("<h1 id=\"advanced-topics\">Advanced Topics</h1>"));
+    assert!(placeholder.contains("<p class=\"draft\">This chapter is not written yet.</p>"));
+
+    let nav = doc.0.get_resource_str_by_path("OEBPS/nav.xhtml").unwrap();
+    assert!(nav.contains("<a href=\"draft-advanced-topics.html\">2. Advanced Topics</a>"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_draft_chapters_book_is_valid() {
+    output_epub_is_valid("draft_chapters_example");
+}
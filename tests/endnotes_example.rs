@@ -0,0 +1,61 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_footnotes_link_to_notes_chapter() {
+    let mut doc = generate_epub("endnotes_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let intro = doc.0.get_resource_str_by_path("OEBPS/intro.html").unwrap();
+    assert!(intro.contains(
+        "<sup class=\"footnote-reference\" id=\"fr-intro-claim-1\"><a epub:type=\"noteref\" href=\"notes.html#fn-intro-claim\">[1]</a></sup>"
+    ));
+    assert!(!intro.contains("Made by many."));
+
+    // numbering continues through the book
+    let one = doc
+        .0
+        .get_resource_str_by_path("OEBPS/part/one.html")
+        .unwrap();
+    assert!(one.contains(
+        "<sup class=\"footnote-reference\" id=\"fr-part-one-claim-2\"><a epub:type=\"noteref\" href=\"../notes.html#fn-part-one-claim\">[3]</a></sup>"
+    ));
+    assert!(!one.contains("class=\"footnotes\""));
+}
+
+#[test]
+#[serial]
+fn test_notes_chapter_groups_notes_by_chapter() {
+    let mut doc = generate_epub("endnotes_example").unwrap();
+    let last = doc.0.spine.last().unwrap();
+    let resource = doc.0.resources.get(&last.idref).unwrap();
+    assert!(resource.path.ends_with("notes.html"));
+
+    let notes = doc.0.get_resource_str_by_path("OEBPS/notes.html").unwrap();
+    debug!("notes = {:?}", notes);
+    assert!(notes.contains("<body class=\"backmatter endnotes\">"));
+    assert!(notes.contains("<h1>Notes</h1>"));
+    assert!(notes.contains("<h2><a href=\"intro.html\">1. Introduction</a></h2>"));
+    assert!(notes.contains("<h2><a href=\"part/one.html\">2. Part One</a></h2>"));
+    assert!(!notes.contains("Conclusion"));
+    assert!(notes.contains(
+        "<div class=\"footnote-definition\" id=\"fn-part-one-claim\" epub:type=\"endnote\">"
+    ));
+    assert!(notes.contains(
+        "Made by a few. <a href=\"part/one.html#fr-part-one-claim-1\">↩</a> <a href=\"part/one.html#fr-part-one-claim-2\">↩2</a>"
+    ));
+
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert!(opf.contains("<reference type=\"notes\" title=\"Notes\" href=\"notes.html\""));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_endnotes_book_is_valid() {
+    output_epub_is_valid("endnotes_example");
+}
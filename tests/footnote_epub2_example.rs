@@ -0,0 +1,38 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_epub2_footnotes_have_plain_links() {
+    let mut doc = generate_epub("footnote_epub2_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains(
+        "<sup class=\"footnote-reference\" id=\"fr-example-1\"><a href=\"#fn-example\">[1]</a></sup> in EPUB 2 books"
+    ));
+    assert!(content.contains(
+        "<div class=\"footnotes\">\n<div class=\"footnote-definition\" id=\"fn-example\"><p><span class=\"footnote-definition-label\">[1]</span>"
+    ));
+    assert!(content.contains("<a href=\"#fr-example-1\">↩</a></p>"));
+    assert!(!content.contains("epub:type"));
+
+    // the definition is moved after the rest of the chapter
+    let definition = content.find("footnote-definition\"").unwrap();
+    let paragraph = content.find("The definition is moved").unwrap();
+    assert!(paragraph < definition);
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_footnote_epub2_book_is_valid() {
+    output_epub_is_valid("footnote_epub2_example");
+}
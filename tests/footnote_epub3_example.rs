@@ -21,7 +21,7 @@ fn test_footnote_has_linked_label() {
     let file = doc.0.get_resource_str_by_path(path);
     let content = file.unwrap();
     debug!("content = {:?}", content);
-    assert!(content.contains("<sup class=\"footnote-reference\" id=\"fr-example-1\"><a href=\"#fn-example\">[1]</a></sup> with back-references"));
+    assert!(content.contains("<sup class=\"footnote-reference\" id=\"fr-example-1\"><a epub:type=\"noteref\" href=\"#fn-example\">[1]</a></sup> with back-references"));
 }
 
 #[test]
@@ -57,7 +57,7 @@ fn test_footnote_definition_label() {
     let file = doc.0.get_resource_str_by_path(path);
     let content = file.unwrap();
     println!("content = \n{:?}", content);
-    assert!(content.contains("<div class=\"footnotes\" epub:type=\"footnotes\">\n<div class=\"footnote-definition\" id=\"fn-example\" epub:type=\"footnote\"><p><span class=\"footnote-definition-label\">[1]</span>"));
+    assert!(content.contains("<div class=\"footnotes\" epub:type=\"footnotes\">\n<aside class=\"footnote-definition\" id=\"fn-example\" epub:type=\"footnote\"><p><span class=\"footnote-definition-label\">[1]</span>"));
 }
 
 #[test]
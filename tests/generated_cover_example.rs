@@ -0,0 +1,42 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_cover_is_generated_from_book_metadata() {
+    let mut doc = generate_epub("generated_cover_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let cover_id = doc.0.get_cover_id().unwrap();
+    let resource = doc.0.resources.get(&cover_id).unwrap();
+    assert!(resource.path.ends_with("cover.svg"));
+    assert_eq!(resource.mime, "image/svg+xml");
+
+    let cover = doc.0.get_resource_str_by_path("OEBPS/cover.svg").unwrap();
+    debug!("cover = {:?}", cover);
+    assert!(cover.contains("fill=\"#203040\""));
+    assert!(cover.contains("<tspan x=\"800\" dy=\"0\">The Generated</tspan>"));
+    assert!(cover.contains("<tspan x=\"800\" dy=\"180\">Cover Example</tspan>"));
+    assert!(cover.contains(">Covers &amp; Thumbnails</tspan>"));
+    assert!(cover.contains(">Jane Doe</text>"));
+    assert!(cover.contains("xlink:href=\"data:image/png;base64,"));
+}
+
+#[test]
+#[serial]
+fn test_cover_page_scales_generated_cover() {
+    let mut doc = generate_epub("generated_cover_example").unwrap();
+    let page = doc.0.get_resource_str_by_path("OEBPS/cover.html").unwrap();
+    assert!(page.contains("viewBox=\"0 0 1600 2400\""));
+    assert!(page.contains("xlink:href=\"cover.svg\""));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_generated_cover_book_is_valid() {
+    output_epub_is_valid("generated_cover_example");
+}
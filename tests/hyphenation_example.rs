@@ -0,0 +1,46 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_long_words_are_hyphenated() {
+    let mut doc = generate_epub("hyphenation_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("<h1 id=\"hyphenation\">Hyphenation</h1>"));
+    assert!(content.contains(
+        "Jus\u{AD}ti\u{AD}fied para\u{AD}graphs need hy\u{AD}phen\u{AD}a\u{AD}tion, ob\u{AD}vi\u{AD}ously."
+    ));
+    assert!(
+        content.contains(
+            "<a href=\"https://example.com\">hyphenation</a> and <code>hyphenation</code>"
+        )
+    );
+}
+
+#[test]
+#[serial]
+fn test_chapter_language_picks_the_dictionary() {
+    let mut doc = generate_epub("hyphenation_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_2.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("Die Sil\u{AD}ben\u{AD}tren\u{AD}nung hilft"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_hyphenation_book_is_valid() {
+    output_epub_is_valid("hyphenation_example");
+}
@@ -0,0 +1,76 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_inline_toc_lists_chapters_and_headings() {
+    let mut doc = generate_epub("inline_toc_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let toc = doc.0.get_resource_str_by_path("OEBPS/toc.html").unwrap();
+    debug!("toc = {:?}", toc);
+    assert!(toc.contains("<body class=\"frontmatter toc\" epub:type=\"toc\">"));
+    assert!(toc.contains(
+        "<ol class=\"toc\">\n<li><a href=\"foreword.html\">Foreword</a></li>\n<li><a href=\"guide/index.html\">1. Guide</a>\n<ol>\n<li><a href=\"guide/index.html#overview\">Overview</a></li>\n<li><a href=\"guide/installation.html\">1.1. Installation</a>\n<ol>\n<li><a href=\"guide/installation.html#from-source\">From source</a></li>\n<li><a href=\"guide/installation.html#from-source-1\">From source</a></li>\n</ol></li>\n</ol></li>\n<li><a href=\"reference.html\">2. Reference</a></li>\n</ol>"
+    ));
+    assert!(!toc.contains("#details"));
+}
+
+#[test]
+#[serial]
+fn test_inline_toc_comes_before_chapters() {
+    let doc = generate_epub("inline_toc_example").unwrap();
+    let first = doc.0.spine.first().unwrap();
+    let resource = doc.0.resources.get(&first.idref).unwrap();
+    assert!(resource.path.ends_with("toc.html"));
+}
+
+#[test]
+#[serial]
+fn test_headings_have_anchor_ids() {
+    let mut doc = generate_epub("inline_toc_example").unwrap();
+    let chapter = doc
+        .0
+        .get_resource_str_by_path("OEBPS/guide/installation.html")
+        .unwrap();
+    assert!(chapter.contains("<h2 id=\"from-source-1\">From source</h2>"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_inline_toc_book_is_valid() {
+    output_epub_is_valid("inline_toc_example");
+}
+
+#[test]
+#[serial]
+fn test_navigation_lists_headings_down_to_toc_depth() {
+    let mut doc = generate_epub("inline_toc_example").unwrap();
+    let nav = doc.0.get_resource_str_by_path("OEBPS/nav.xhtml").unwrap();
+    debug!("nav = {:?}", nav);
+    assert!(nav.contains("href=\"guide/index.html#overview\""));
+    assert!(nav.contains("href=\"guide/index.html#details\""));
+    assert!(nav.contains("href=\"guide/installation.html#from-source-1\""));
+    let ncx = doc.0.get_resource_str_by_path("OEBPS/toc.ncx").unwrap();
+    assert!(ncx.contains("<content src=\"guide/index.html#details\"/>"));
+}
+
+#[test]
+#[serial]
+fn test_links_to_chapter_anchors() {
+    let mut doc = generate_epub("inline_toc_example").unwrap();
+    let chapter = doc
+        .0
+        .get_resource_str_by_path("OEBPS/guide/installation.html")
+        .unwrap();
+    assert!(chapter.contains("<a href=\"../reference.html#lookup\">the lookup table</a>"));
+    let reference = doc
+        .0
+        .get_resource_str_by_path("OEBPS/reference.html")
+        .unwrap();
+    assert!(reference.contains("<h3 id=\"lookup\">Lookup table</h3>"));
+}
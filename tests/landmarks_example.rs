@@ -0,0 +1,72 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+fn nav_content() -> String {
+    let mut doc = generate_epub("landmarks_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    doc.0.get_resource_str_by_path("OEBPS/nav.xhtml").unwrap()
+}
+
+#[test]
+#[serial]
+fn test_landmarks_list_start_of_content_and_back_matter() {
+    let content = nav_content();
+    debug!("nav = {:?}", content);
+    let landmarks = &content[content.find("\"landmarks\"").unwrap()..];
+    assert!(landmarks.contains("<a epub:type=\"toc\" href=\"nav.xhtml#toc\">"));
+    assert!(landmarks.contains("<a epub:type=\"bodymatter\" href=\"getting_started.html\">"));
+    assert!(landmarks.contains("<a epub:type=\"backmatter\" href=\"appendix.html\">"));
+    assert!(landmarks.contains("<a epub:type=\"index\" href=\"index_page.html\">"));
+    assert!(landmarks.contains("<a epub:type=\"frontmatter\" href=\"preface.html\">"));
+    assert_eq!(landmarks.matches("backmatter").count(), 1);
+}
+
+#[test]
+#[serial]
+fn test_guide_starts_at_first_numbered_chapter() {
+    let mut doc = generate_epub("landmarks_example").unwrap();
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    debug!("opf = {:?}", opf);
+    assert!(opf.contains(
+        "<reference type=\"text\" title=\"1. Getting Started\" href=\"getting_started.html\"/>"
+    ));
+    assert!(opf.contains("<reference type=\"index\" title=\"Index\" href=\"index_page.html\"/>"));
+    assert!(opf.contains(
+        "<reference type=\"other.frontmatter\" title=\"Preface\" href=\"preface.html\"/>"
+    ));
+    assert!(opf.contains(
+        "<reference type=\"other.backmatter\" title=\"Appendix\" href=\"appendix.html\"/>"
+    ));
+}
+
+#[test]
+#[serial]
+fn test_prefix_and_suffix_chapters_are_front_and_back_matter() {
+    let mut doc = generate_epub("landmarks_example").unwrap();
+    let preface = doc
+        .0
+        .get_resource_str_by_path("OEBPS/preface.html")
+        .unwrap();
+    assert!(preface.contains("<body class=\"frontmatter\" epub:type=\"frontmatter\">"));
+    let chapter = doc
+        .0
+        .get_resource_str_by_path("OEBPS/getting_started.html")
+        .unwrap();
+    assert!(chapter.contains("<body class=\"bodymatter\">"));
+    let appendix = doc
+        .0
+        .get_resource_str_by_path("OEBPS/appendix.html")
+        .unwrap();
+    assert!(appendix.contains("<body class=\"backmatter appendix\" epub:type=\"appendix\">"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_landmarks_book_is_valid() {
+    output_epub_is_valid("landmarks_example");
+}
@@ -17,7 +17,7 @@ fn test_output_long_book_exists() {
         "{}",
         format!(
             "output_file is incorrect !: {}",
-            output_file.unwrap().display().to_string()
+            output_file.unwrap().display()
         )
     );
     let output_file = output_file.unwrap();
@@ -0,0 +1,41 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_configured_extensions() {
+    let mut doc = generate_epub("markdown_extensions_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("Raised <sup>up</sup> and lowered <sub>down</sub>"));
+    // the HTML renderer's settings are followed
+    assert!(content.contains("said \"Einstein\"."));
+    assert!(content.contains("<dl>\n<dt>Term</dt>\n<dd>The definition.</dd>\n</dl>"));
+}
+
+#[test]
+#[serial]
+fn test_assets_are_found_with_the_same_extensions() {
+    let mut doc = generate_epub("markdown_extensions_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    assert!(content.contains("<img src=\"diagram.png\""));
+    assert!(doc.0.get_resource_by_path("OEBPS/diagram.png").is_some());
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_markdown_extensions_book_is_valid() {
+    output_epub_is_valid("markdown_extensions_example");
+}
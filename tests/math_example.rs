@@ -0,0 +1,55 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_tex_is_converted_to_mathml() {
+    let mut doc = generate_epub("math_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains(
+        "The area of a circle is <math xmlns=\"http://www.w3.org/1998/Math/MathML\"><semantics>"
+    ));
+    assert!(content.contains("<annotation encoding=\"application/x-tex\">\\pi r^2</annotation>"));
+    assert!(
+        content.contains("<math xmlns=\"http://www.w3.org/1998/Math/MathML\" display=\"block\">")
+    );
+    assert!(
+        content
+            .contains("<annotation encoding=\"application/x-tex\">e^{i\\pi} + 1 = 0</annotation>")
+    );
+    assert!(!content.contains("circle is \\("));
+    assert!(!content.contains("$$"));
+    // code blocks are left alone
+    assert!(content.contains("<pre><code class=\"language-tex\">\\( left alone \\)"));
+}
+
+#[test]
+#[serial]
+fn test_chapters_with_math_have_mathml_property() {
+    let mut doc = generate_epub("math_example").unwrap();
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    debug!("opf = {:?}", opf);
+    let item = |href: &str| {
+        let end = opf.find(&format!("href=\"{href}\"")).unwrap();
+        let start = opf[..end].rfind("<item ").unwrap();
+        opf[start..end].to_string()
+    };
+    assert!(item("chapter_1.html").contains("properties=\"mathml\""));
+    assert!(!item("chapter_2.html").contains("properties"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_math_book_is_valid() {
+    output_epub_is_valid("math_example");
+}
@@ -0,0 +1,31 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_runnable_code_blocks_link_to_the_playground() {
+    let mut doc = generate_epub("playground_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert_eq!(content.matches("<p class=\"playground\">").count(), 2);
+    assert!(content.contains(
+        "<a href=\"https://play.rust-lang.org/?version=stable&amp;mode=debug&amp;edition=2021&amp;code=%23%21%5Ballow%28unused%29%5D%0Afn+main%28%29+%7B%0Ause+std%3A%3Acollections%3A%3AHashMap%3B%0A"
+    ));
+    assert!(content.contains("&amp;edition=2024&amp;code=fn+main%28%29+%7B%0A"));
+    assert!(content.contains(">Run this code</a></p>"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_playground_book_is_valid() {
+    output_epub_is_valid("playground_example");
+}
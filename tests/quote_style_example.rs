@@ -0,0 +1,39 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_quotes_follow_book_language() {
+    let mut doc = generate_epub("quote_style_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("Er sagte „Hallo <em>Welt</em>“ und ging in den ’90ern nach Hause."));
+    assert!(content.contains("(mit „Klammern“) und ‚einfachen‘ Anführungszeichen."));
+}
+
+#[test]
+#[serial]
+fn test_quotes_follow_chapter_language() {
+    let mut doc = generate_epub("quote_style_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_2.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("Il a dit «\u{202F}bonjour\u{202F}» en partant."));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_quote_style_book_is_valid() {
+    output_epub_is_valid("quote_style_example");
+}
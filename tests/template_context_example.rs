@@ -0,0 +1,75 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_template_gets_chapter_and_book() {
+    let mut doc = generate_epub("template_context_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let setup = doc
+        .0
+        .get_resource_str_by_path("OEBPS/guide/setup.html")
+        .unwrap();
+    debug!("setup = {:?}", setup);
+    assert!(setup.contains("<title>1.1. Setup - The Template Context Example Book</title>"));
+    assert!(setup.contains(
+        "<p class=\"breadcrumbs\">The Template Context Example Book / Guide / Setup</p>"
+    ));
+    assert!(setup.contains(
+        "What templates can see by Jane Doe, John Roe; path guide/setup.html from guide/setup.md at depth 1; toc depth 1"
+    ));
+}
+
+#[test]
+#[serial]
+fn test_template_gets_previous_and_next_chapter() {
+    let mut doc = generate_epub("template_context_example").unwrap();
+    let guide = doc
+        .0
+        .get_resource_str_by_path("OEBPS/guide/README.html")
+        .unwrap();
+    assert!(!guide.contains("class=\"previous\""));
+    assert!(guide.contains("<a class=\"next\" href=\"../guide/setup.html\">Setup</a>"));
+
+    // the draft chapter is skipped
+    let setup = doc
+        .0
+        .get_resource_str_by_path("OEBPS/guide/setup.html")
+        .unwrap();
+    assert!(setup.contains("<a class=\"previous\" href=\"../guide/README.html\">Guide</a>"));
+    assert!(setup.contains("<a class=\"next\" href=\"../reference.html\">Reference</a>"));
+
+    let reference = doc
+        .0
+        .get_resource_str_by_path("OEBPS/reference.html")
+        .unwrap();
+    assert!(reference.contains("<a class=\"previous\" href=\"guide/setup.html\">Setup</a>"));
+    assert!(!reference.contains("class=\"next\""));
+}
+
+#[test]
+#[serial]
+fn test_inline_toc_gets_the_same_context() {
+    let mut doc = generate_epub("template_context_example").unwrap();
+    let toc = doc.0.get_resource_str_by_path("OEBPS/toc.html").unwrap();
+    debug!("toc = {:?}", toc);
+    assert!(toc.contains("<title> Table Of Contents - The Template Context Example Book</title>"));
+    assert!(toc.contains(
+        "<p class=\"breadcrumbs\">The Template Context Example Book / Table Of Contents</p>"
+    ));
+    assert!(toc.contains(
+        "What templates can see by Jane Doe, John Roe; path toc.html from  at depth 0; toc depth 1"
+    ));
+    assert!(toc.contains("<body class=\"frontmatter toc\">"));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_template_context_book_is_valid() {
+    output_epub_is_valid("template_context_example");
+}
@@ -0,0 +1,47 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_chapter_template_picked_by_pattern() {
+    let mut doc = generate_epub("theme_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let glossary = doc
+        .0
+        .get_resource_str_by_path("OEBPS/appendix/glossary.html")
+        .unwrap();
+    debug!("glossary = {:?}", glossary);
+    assert!(glossary.contains("<body class=\"appendix\" id=\"the-glossary\">"));
+
+    let chapter = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    assert!(chapter.contains("<body class=\"bodymatter\">"));
+}
+
+#[test]
+#[serial]
+fn test_theme_partials_and_helpers() {
+    let mut doc = generate_epub("theme_example").unwrap();
+    let glossary = doc
+        .0
+        .get_resource_str_by_path("OEBPS/appendix/glossary.html")
+        .unwrap();
+    assert!(glossary.contains("<title>The Glossary</title>"));
+    assert!(glossary.contains("<link rel=\"stylesheet\" href=\"../stylesheet.css\" />"));
+    assert!(glossary.contains(
+        "<p class=\"footer\"><img src=\"../images/logo.png\" alt=\"Logo\" /> Published May 2024</p>"
+    ));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_theme_book_is_valid() {
+    output_epub_is_valid("theme_example");
+}
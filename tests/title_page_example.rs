@@ -0,0 +1,97 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_title_page_shows_book_metadata() {
+    let mut doc = generate_epub("title_page_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let page = doc
+        .0
+        .get_resource_str_by_path("OEBPS/title_page.html")
+        .unwrap();
+    debug!("title page = {:?}", page);
+    assert!(page.contains("epub:type=\"frontmatter titlepage\""));
+    assert!(page.contains("<h1 class=\"title\">TitlePageExample</h1>"));
+    assert!(page.contains("<p class=\"subtitle\">A Book With Front Pages</p>"));
+    assert!(page.contains("<p class=\"authors\">Jane Doe, John Roe</p>"));
+    assert!(page.contains("<img src=\"assets/logo.png\" alt=\"Example Press\" />"));
+    assert!(
+        doc.0
+            .get_resource_by_path("OEBPS/assets/logo.png")
+            .is_some()
+    );
+}
+
+#[test]
+#[serial]
+fn test_copyright_page_shows_rights() {
+    let mut doc = generate_epub("title_page_example").unwrap();
+    let page = doc
+        .0
+        .get_resource_str_by_path("OEBPS/copyright.html")
+        .unwrap();
+    debug!("copyright page = {:?}", page);
+    assert!(page.contains("<p class=\"rights\">Copyright © 2026 Jane Doe &amp; John Roe</p>"));
+    assert!(page.contains("<p class=\"isbn\">ISBN 978-3-16-148410-0</p>"));
+    assert!(page.contains("<p class=\"generator\">Generated by mdbook-epub"));
+}
+
+#[test]
+#[serial]
+fn test_generated_pages_come_first() {
+    let mut doc = generate_epub("title_page_example").unwrap();
+    let spine: Vec<String> = doc
+        .0
+        .spine
+        .iter()
+        .map(|item| {
+            let resource = doc.0.resources.get(&item.idref).unwrap();
+            resource
+                .path
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(
+        spine,
+        vec![
+            "title_page.html",
+            "copyright.html",
+            "toc.html",
+            "chapter_1.html"
+        ]
+    );
+
+    let nav = doc.0.get_resource_str_by_path("OEBPS/nav.xhtml").unwrap();
+    assert!(nav.contains("<a epub:type=\"titlepage\" href=\"title_page.html\">"));
+    assert!(nav.contains("<a epub:type=\"copyright-page\" href=\"copyright.html\">"));
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert!(opf.contains("<reference type=\"title-page\""));
+}
+
+#[test]
+#[serial]
+fn test_logo_shown_in_a_chapter_is_added_once() {
+    let mut doc = generate_epub("title_page_example").unwrap();
+    let chapter = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    assert!(chapter.contains("<img src=\"assets/logo.png\" alt=\"Example Press\""));
+    let opf = doc.0.get_resource_str_by_path("OEBPS/content.opf").unwrap();
+    assert_eq!(opf.matches("href=\"assets/logo.png\"").count(), 1);
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_title_page_book_is_valid() {
+    output_epub_is_valid("title_page_example");
+}
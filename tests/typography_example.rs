@@ -0,0 +1,42 @@
+use serial_test::serial;
+use tracing::debug;
+
+mod common;
+use crate::common::epub::output_epub_is_valid;
+use common::epub::generate_epub;
+
+#[test]
+#[serial]
+fn test_smart_punctuation() {
+    let mut doc = generate_epub("typography_example").unwrap();
+    debug!("doc current path = {:?}", doc.1);
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains(
+        "Il a dit «\u{202F}bonjour\u{202F}» — puis il est parti… Vraiment\u{202F}! Pourquoi\u{202F}? Voici\u{A0}: rien."
+    ));
+    assert!(content.contains("Les pages 10–20 pèsent 5\u{A0}kg\u{202F}; <code>cargo build --release...</code> reste tel quel."));
+}
+
+#[test]
+#[serial]
+fn test_code_and_html_are_untouched() {
+    let mut doc = generate_epub("typography_example").unwrap();
+    let content = doc
+        .0
+        .get_resource_str_by_path("OEBPS/chapter_1.html")
+        .unwrap();
+    debug!("content = {:?}", content);
+    assert!(content.contains("<span title=\"a--b...\">HTML</span>"));
+    assert!(content.contains("cargo run -- --verbose \"x\"..."));
+}
+
+#[ignore = "CI/CD only"]
+#[test]
+#[serial]
+fn test_output_typography_book_is_valid() {
+    output_epub_is_valid("typography_example");
+}